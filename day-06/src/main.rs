@@ -1,3 +1,6 @@
+mod big_uint;
+
+use big_uint::BigUint;
 use std::env;
 use std::fs::read_to_string;
 use std::path::Path;
@@ -6,18 +9,129 @@ fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
-    let initial_pop = parse_population_by_timer(&content)?;
+    let options = parse_options(env::args().skip(2))?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
+
+    if options.life_cycle == LifeCycle::default() {
+        let initial_pop = parse_population_by_timer(&content)?;
+
+        let population_80 = run_days(initial_pop, 80);
+        println!("After 80 days, there are {} lanternfish", population_80);
+
+        let population_256 = run_days(initial_pop, 256);
+        println!("After 256 days, there are {} lanternfish", population_256);
+    }
 
-    let population_80 = run_days(initial_pop, 80);
-    println!("After 80 days, there are {} lanternfish", population_80);
+    let initial_pop = parse_population(&content, &options.life_cycle)?;
 
-    let population_256 = run_days(initial_pop, 256);
-    println!("After 256 days, there are {} lanternfish", population_256);
+    if let Some(days) = options.days {
+        let population = population_after_days(&initial_pop, &options.life_cycle, days);
+        println!("After {} days, there are {} lanternfish", days, population);
+    }
+
+    if let Some(days) = options.series_days {
+        let header: Vec<String> = (0..options.life_cycle.n_timers())
+            .map(|timer| format!("timer_{}", timer))
+            .collect();
+        println!("day,{},total", header.join(","));
+        for (day, population) in population_time_series(&initial_pop, &options.life_cycle, days)
+            .iter()
+            .enumerate()
+        {
+            let counts: Vec<String> = population.iter().map(|c| c.to_string()).collect();
+            println!(
+                "{},{},{}",
+                day,
+                counts.join(","),
+                population.iter().fold(BigUint::zero(), |sum, c| &sum + c)
+            );
+        }
+    }
 
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LifeCycle {
+    // days between two spawns of an adult lanternfish
+    cycle_length: usize,
+    // additional days a newborn lanternfish needs before its first cycle
+    newborn_delay: usize,
+    // number of new lanternfish spawned at the end of each cycle
+    offspring: u64,
+}
+
+impl Default for LifeCycle {
+    fn default() -> LifeCycle {
+        LifeCycle {
+            cycle_length: 7,
+            newborn_delay: 2,
+            offspring: 1,
+        }
+    }
+}
+
+impl LifeCycle {
+    fn n_timers(&self) -> usize {
+        self.cycle_length + self.newborn_delay
+    }
+
+    fn adult_timer(&self) -> usize {
+        self.cycle_length - 1
+    }
+
+    fn newborn_timer(&self) -> usize {
+        self.n_timers() - 1
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+struct Options {
+    life_cycle: LifeCycle,
+    days: Option<u64>,
+    series_days: Option<usize>,
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--cycle=") {
+            options.life_cycle.cycle_length = parse_option_value(value, "--cycle")?;
+            if options.life_cycle.cycle_length == 0 {
+                return Err("The cycle length must be at least 1".to_owned());
+            }
+        } else if let Some(value) = arg.strip_prefix("--delay=") {
+            options.life_cycle.newborn_delay = parse_option_value(value, "--delay")?;
+        } else if let Some(value) = arg.strip_prefix("--offspring=") {
+            options.life_cycle.offspring = parse_option_value(value, "--offspring")?;
+        } else if let Some(value) = arg.strip_prefix("--series=") {
+            options.series_days = Some(parse_option_value(value, "--series")?);
+        } else {
+            let days: u64 = arg
+                .parse()
+                .map_err(|e| format!("Unable to parse number of days: {}", e))?;
+            if days > MAX_DAYS {
+                return Err(format!(
+                    "Expected at most {} days, got {}: the number of lanternfish would not fit into memory",
+                    MAX_DAYS, days
+                ));
+            }
+            options.days = Some(days);
+        }
+    }
+    Ok(options)
+}
+
+fn parse_option_value<T>(value: &str, name: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("Unable to parse value of {}: {}", name, e))
+}
+
 fn run_days(mut population: [u64; 9], days: usize) -> u64 {
     for _ in 0..days {
         population = next_day(population);
@@ -38,23 +152,145 @@ fn next_day(population: [u64; 9]) -> [u64; 9] {
     next_pop
 }
 
-fn parse_population_by_timer(input: &str) -> Result<[u64; 9], String> {
-    let mut pop: [u64; 9] = [0; 9];
-    for result in input.split(',').map(|s| {
-        s.trim()
-            .parse::<usize>()
-            .map_err(|e| format!("Unable to parse input: {}", e))
-    }) {
-        // should have don this with a `fold()`…
-        let timer = result?;
-        if timer > 8 {
-            return Err(format!("Expected no timer greater than 8, got {}", timer));
+fn next_day_with_life_cycle(population: &[BigUint], life_cycle: &LifeCycle) -> Vec<BigUint> {
+    let mut next_pop: Vec<BigUint> = vec![BigUint::zero(); life_cycle.n_timers()];
+
+    next_pop[..population.len() - 1].clone_from_slice(&population[1..]);
+    let adult = life_cycle.adult_timer();
+    next_pop[adult] = &next_pop[adult] + &population[0];
+    let newborn = life_cycle.newborn_timer();
+    next_pop[newborn] =
+        &next_pop[newborn] + &(&population[0] * &BigUint::from(life_cycle.offspring));
+
+    next_pop
+}
+
+// population by timer for each day, starting with the initial population on day 0
+fn population_time_series(
+    initial_population: &[u64],
+    life_cycle: &LifeCycle,
+    days: usize,
+) -> Vec<Vec<BigUint>> {
+    let mut series: Vec<Vec<BigUint>> = Vec::with_capacity(days + 1);
+    series.push(
+        initial_population
+            .iter()
+            .map(|p| BigUint::from(*p))
+            .collect(),
+    );
+    for day in 0..days {
+        series.push(next_day_with_life_cycle(&series[day], life_cycle));
+    }
+    series
+}
+
+type Matrix = Vec<Vec<BigUint>>;
+
+// The population grows exponentially, so the number of digits grows linearly with the days. With
+// the default life cycle, a million days take a few seconds and result in a number with about
+// 38000 digits. Far beyond that, the multiplications of the last matrix products take forever.
+const MAX_DAYS: u64 = 1_000_000;
+
+// the same rules as in `next_day_with_life_cycle`, as a matrix where
+// `next[i] = sum(m[i][j] * population[j])`
+fn transition_matrix(life_cycle: &LifeCycle) -> Matrix {
+    let size = life_cycle.n_timers();
+    let mut m: Matrix = vec![vec![BigUint::zero(); size]; size];
+    for (i, row) in m.iter_mut().enumerate().take(size - 1) {
+        row[i + 1] = BigUint::from(1);
+    }
+    m[life_cycle.adult_timer()][0] = &m[life_cycle.adult_timer()][0] + &BigUint::from(1);
+    m[life_cycle.newborn_timer()][0] =
+        &m[life_cycle.newborn_timer()][0] + &BigUint::from(life_cycle.offspring);
+    m
+}
+
+fn identity_matrix(size: usize) -> Matrix {
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| BigUint::from(if i == j { 1 } else { 0 }))
+                .collect()
+        })
+        .collect()
+}
+
+fn mul_matrices(a: &Matrix, b: &Matrix) -> Matrix {
+    a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|j| {
+                    row.iter()
+                        .zip(b.iter())
+                        .filter(|(x, b_row)| !x.is_zero() && !b_row[j].is_zero())
+                        .fold(BigUint::zero(), |sum, (x, b_row)| &sum + &(x * &b_row[j]))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn mul_matrix_vector(m: &Matrix, v: &[BigUint]) -> Vec<BigUint> {
+    m.iter()
+        .map(|row| {
+            row.iter()
+                .zip(v)
+                .filter(|(x, y)| !x.is_zero() && !y.is_zero())
+                .fold(BigUint::zero(), |sum, (x, y)| &sum + &(x * y))
+        })
+        .collect()
+}
+
+// Exponentiation by squaring of the transition matrix, so this needs O(log(days)) matrix products.
+// See `MAX_DAYS` for how far this can go.
+fn population_after_days(population: &[u64], life_cycle: &LifeCycle, days: u64) -> BigUint {
+    let mut result = identity_matrix(life_cycle.n_timers());
+    let mut power = transition_matrix(life_cycle);
+    let mut remaining_days = days;
+    while remaining_days > 0 {
+        if remaining_days & 1 == 1 {
+            result = mul_matrices(&result, &power);
+        }
+        remaining_days >>= 1;
+        // the last squaring would be thrown away anyway, and it is the most expensive one
+        if remaining_days > 0 {
+            power = mul_matrices(&power, &power);
         }
-        pop[timer] += 1;
     }
+    let population: Vec<BigUint> = population.iter().map(|p| BigUint::from(*p)).collect();
+    mul_matrix_vector(&result, &population)
+        .iter()
+        .fold(BigUint::zero(), |sum, p| &sum + p)
+}
+
+fn parse_population_by_timer(input: &str) -> Result<[u64; 9], String> {
+    let mut pop: [u64; 9] = [0; 9];
+    pop.copy_from_slice(&parse_population(input, &LifeCycle::default())?);
     Ok(pop)
 }
 
+fn parse_population(input: &str, life_cycle: &LifeCycle) -> Result<Vec<u64>, String> {
+    let max_timer = life_cycle.newborn_timer();
+    input
+        .split(',')
+        .map(|s| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Unable to parse input: {}", e))
+        })
+        .try_fold(vec![0; life_cycle.n_timers()], |mut pop, timer| {
+            let timer = timer?;
+            if timer > max_timer {
+                return Err(format!(
+                    "Expected no timer greater than {}, got {}",
+                    max_timer, timer
+                ));
+            }
+            pop[timer] += 1;
+            Ok(pop)
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -85,4 +321,170 @@ mod test {
         assert_eq!(pop_count_18, 26);
         assert_eq!(pop_count_80, 5934);
     }
+
+    #[test]
+    fn population_after_days_matches_run_days() {
+        // given
+        let initial_population =
+            parse_population_by_timer("3,4,3,1,2\n").expect("Expected sucessful parsing");
+
+        for days in [0, 1, 18, 80, 256, 400] {
+            // when
+            let pop_count = population_after_days(&initial_population, &LifeCycle::default(), days);
+
+            // then
+            assert_eq!(
+                pop_count.to_string(),
+                run_days(initial_population, days as usize).to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn population_after_days_works_beyond_u64() {
+        // given
+        let initial_population =
+            parse_population_by_timer("3,4,3,1,2\n").expect("Expected sucessful parsing");
+        // u128 is enough for 900 days, so count them the slow way for comparison
+        let mut expected: [u128; 9] = [0; 9];
+        for (count, initial) in expected.iter_mut().zip(initial_population) {
+            *count = initial as u128;
+        }
+        for _ in 0..900 {
+            expected.rotate_left(1);
+            expected[6] += expected[8];
+        }
+        let expected: u128 = expected.iter().sum();
+
+        // when
+        let pop_count = population_after_days(&initial_population, &LifeCycle::default(), 900);
+
+        // then
+        assert!(expected > u64::MAX as u128);
+        assert_eq!(pop_count.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn parse_options_rejects_too_many_days() {
+        // given
+        let args = ["1000001".to_owned()];
+
+        // when
+        let options = parse_options(args.into_iter());
+
+        // then
+        assert!(options.is_err());
+    }
+
+    #[test]
+    fn next_day_with_life_cycle_matches_next_day_for_default() {
+        // given
+        let population: [u64; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let big_population: Vec<BigUint> = population.iter().map(|p| BigUint::from(*p)).collect();
+
+        // when
+        let next_pop = next_day_with_life_cycle(&big_population, &LifeCycle::default());
+
+        // then
+        let expected: Vec<BigUint> = next_day(population)
+            .iter()
+            .map(|p| BigUint::from(*p))
+            .collect();
+        assert_eq!(next_pop, expected);
+    }
+
+    #[test]
+    fn population_time_series_works_for_custom_life_cycle() {
+        // given
+        let life_cycle = LifeCycle {
+            cycle_length: 2,
+            newborn_delay: 1,
+            offspring: 2,
+        };
+        let initial_population =
+            parse_population("0,2\n", &life_cycle).expect("Expected sucessful parsing");
+
+        // when
+        let series = population_time_series(&initial_population, &life_cycle, 3);
+
+        // then
+        let expected: Vec<Vec<BigUint>> = [[1, 0, 1], [0, 2, 2], [2, 2, 0], [2, 2, 4]]
+            .iter()
+            .map(|day| day.iter().map(|p| BigUint::from(*p)).collect())
+            .collect();
+        assert_eq!(series, expected);
+    }
+
+    #[test]
+    fn population_after_days_works_for_custom_life_cycle() {
+        // given
+        let life_cycle = LifeCycle {
+            cycle_length: 3,
+            newborn_delay: 0,
+            offspring: 3,
+        };
+        let initial_population =
+            parse_population("0,1,2\n", &life_cycle).expect("Expected sucessful parsing");
+
+        // when
+        let series = population_time_series(&initial_population, &life_cycle, 30);
+        let pop_count = population_after_days(&initial_population, &life_cycle, 30);
+
+        // then
+        assert_eq!(
+            pop_count,
+            series[30]
+                .iter()
+                .fold(BigUint::zero(), |sum, count| &sum + count)
+        );
+    }
+
+    #[test]
+    fn population_time_series_works_beyond_u64() {
+        // given
+        let initial_population =
+            parse_population_by_timer("3,4,3,1,2\n").expect("Expected sucessful parsing");
+        let life_cycle = LifeCycle {
+            offspring: u64::MAX,
+            ..LifeCycle::default()
+        };
+
+        // when
+        let series = population_time_series(&initial_population, &life_cycle, 500);
+        let default_series =
+            population_time_series(&initial_population, &LifeCycle::default(), 900);
+
+        // then
+        assert_eq!(
+            series[500]
+                .iter()
+                .fold(BigUint::zero(), |sum, count| &sum + count),
+            population_after_days(&initial_population, &life_cycle, 500)
+        );
+        assert_eq!(
+            default_series[900]
+                .iter()
+                .fold(BigUint::zero(), |sum, count| &sum + count),
+            population_after_days(&initial_population, &LifeCycle::default(), 900)
+        );
+    }
+
+    #[test]
+    fn parse_population_rejects_timers_beyond_life_cycle() {
+        // given
+        let life_cycle = LifeCycle {
+            cycle_length: 3,
+            newborn_delay: 1,
+            offspring: 1,
+        };
+
+        // when
+        let pop = parse_population("1,4\n", &life_cycle);
+
+        // then
+        assert_eq!(
+            pop,
+            Err("Expected no timer greater than 3, got 4".to_owned())
+        );
+    }
 }
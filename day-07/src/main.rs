@@ -6,76 +6,260 @@ fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
     let initial_positions = parse(&content)?;
 
-    let alignment_fuel = brute_force_alignment(&initial_positions);
-    println!(
-        "The minimal total fuel usage for aligning the crabsubs is {}",
-        alignment_fuel
-    );
+    match median_alignment(&initial_positions) {
+        Ok(alignment_fuel) => println!(
+            "The minimal total fuel usage for aligning the crabsubs is {}",
+            alignment_fuel
+        ),
+        Err(e) => println!("Unable to align the crabsubs: {}", e),
+    }
+
+    match mean_alignment(&initial_positions) {
+        Ok(squared_fuel) => println!(
+            "The minimal total fuel usage for the squared cost is {}",
+            squared_fuel
+        ),
+        Err(e) => println!("Unable to align the crabsubs for the squared cost: {}", e),
+    }
+
+    // optional second file: one weight per crab, in the same format as the positions
+    let weights: Option<Vec<u64>> = match env::args().nth(2) {
+        Some(weights_filename) => Some(parse(
+            &read_to_string(Path::new(&weights_filename)).map_err(|e| e.to_string())?,
+        )?),
+        None => None,
+    };
+    if let Some(weights) = &weights {
+        if weights.len() != initial_positions.len() {
+            return Err(format!(
+                "Expected {} weights, got {}",
+                initial_positions.len(),
+                weights.len()
+            ));
+        }
+    }
 
-    let squared_fuel = brute_force_squared_cost_alignment(&initial_positions);
-    println!(
-        "The minimal total fuel usage for the squared cost is {}",
-        squared_fuel
-    );
+    let mut cost_functions: Vec<(&str, Box<dyn CostFunction>)> = vec![
+        ("linear", Box::new(Linear)),
+        ("triangular", Box::new(Triangular)),
+        ("quadratic", Box::new(Quadratic)),
+    ];
+    if let Some(weights) = weights {
+        cost_functions.push((
+            "weighted linear",
+            Box::new(Weighted {
+                weights: weights.clone(),
+                inner: Linear,
+            }),
+        ));
+        cost_functions.push((
+            "weighted triangular",
+            Box::new(Weighted {
+                weights,
+                inner: Triangular,
+            }),
+        ));
+    }
+    for (name, cost_function) in cost_functions {
+        match optimise_alignment(&initial_positions, cost_function.as_ref()) {
+            Ok(Some((position, cost))) => println!(
+                "Best position for {} cost is {} with a total fuel usage of {}",
+                name, position, cost
+            ),
+            Ok(None) => (),
+            Err(e) => println!("Unable to optimise {} cost: {}", name, e),
+        }
+    }
 
     Ok(())
 }
 
-fn brute_force_alignment(positions: &[u32]) -> u32 {
-    let min: u32 = positions.iter().min().copied().unwrap_or(0);
-    let max: u32 = positions.iter().max().copied().unwrap_or(0);
-
-    (min..=max)
-        .map(|align_pos| {
-            positions
-                .iter()
-                .map(|pos| {
-                    if *pos < align_pos {
-                        align_pos - pos
-                    } else {
-                        pos - align_pos
-                    }
-                })
-                .sum()
+// Fuel cost of a single crab moving a given distance. Costs must not decrease with the distance.
+// `None` means the cost does not fit into u128.
+trait CostFunction {
+    fn cost(&self, crab: usize, distance: u64) -> Option<u128>;
+
+    // if the cost is convex in the distance, so is the total cost in the alignment position
+    fn is_convex(&self) -> bool;
+}
+
+struct Linear;
+
+impl CostFunction for Linear {
+    fn cost(&self, _: usize, distance: u64) -> Option<u128> {
+        Some(distance as u128)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+struct Triangular;
+
+impl CostFunction for Triangular {
+    fn cost(&self, _: usize, distance: u64) -> Option<u128> {
+        Some(fuel_usage(distance))
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+struct Quadratic;
+
+impl CostFunction for Quadratic {
+    fn cost(&self, _: usize, distance: u64) -> Option<u128> {
+        Some(distance as u128 * distance as u128)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+// some crabsubs are heavier than others
+struct Weighted<C: CostFunction> {
+    weights: Vec<u64>,
+    inner: C,
+}
+
+impl<C: CostFunction> CostFunction for Weighted<C> {
+    fn cost(&self, crab: usize, distance: u64) -> Option<u128> {
+        let weight = self.weights.get(crab).copied().unwrap_or(1) as u128;
+        weight.checked_mul(self.inner.cost(crab, distance)?)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.inner.is_convex()
+    }
+}
+
+fn total_cost<C: CostFunction + ?Sized>(
+    positions: &[u64],
+    cost_function: &C,
+    align_pos: u64,
+) -> Result<u128, String> {
+    positions
+        .iter()
+        .enumerate()
+        .try_fold(0u128, |sum, (crab, pos)| {
+            sum.checked_add(cost_function.cost(crab, pos.abs_diff(align_pos))?)
         })
-        .min()
-        .unwrap_or(0)
-}
-
-fn brute_force_squared_cost_alignment(positions: &[u32]) -> u32 {
-    let min: u32 = positions.iter().min().copied().unwrap_or(0);
-    let max: u32 = positions.iter().max().copied().unwrap_or(0);
-
-    (min..=max)
-        .map(|align_pos| {
-            positions
-                .iter()
-                .map(|pos| {
-                    if *pos < align_pos {
-                        fuel_usage(align_pos - pos)
-                    } else {
-                        fuel_usage(pos - align_pos)
-                    }
-                })
-                .sum()
+        .ok_or_else(|| {
+            format!(
+                "the total cost at position {} does not fit into 128 bits",
+                align_pos
+            )
         })
+}
+
+// Returns the best alignment position and its total cost, or `None` if there are no crabs.
+// Since costs do not decrease with the distance, the optimum is always between the outermost crabs.
+// For convex costs, this is a binary search on the slope of the total cost, otherwise a full scan.
+// Totals that do not fit are an error, since the search can not tell what is optimal without them.
+fn optimise_alignment<C: CostFunction + ?Sized>(
+    positions: &[u64],
+    cost_function: &C,
+) -> Result<Option<(u64, u128)>, String> {
+    let (min, max) = match (positions.iter().min(), positions.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return Ok(None),
+    };
+
+    if cost_function.is_convex() {
+        let mut low = min;
+        let mut high = max;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if total_cost(positions, cost_function, mid)?
+                <= total_cost(positions, cost_function, mid + 1)?
+            {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(Some((low, total_cost(positions, cost_function, low)?)))
+    } else {
+        let mut best: Option<(u64, u128)> = None;
+        for align_pos in min..=max {
+            let cost = total_cost(positions, cost_function, align_pos)?;
+            if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                best = Some((align_pos, cost));
+            }
+        }
+        Ok(best)
+    }
+}
+
+// The sum of absolute distances is minimal at the median.
+fn median_alignment(positions: &[u64]) -> Result<u64, String> {
+    let mut sorted = positions.to_vec();
+    sorted.sort_unstable();
+    match sorted.get(sorted.len() / 2) {
+        Some(median) => fuel_total(linear_cost(&sorted, *median)),
+        None => Ok(0),
+    }
+}
+
+// there can not be more than 2^64 crabs, so the sum of u64 distances always fits into u128
+fn linear_cost(positions: &[u64], align_pos: u64) -> u128 {
+    positions
+        .iter()
+        .map(|pos| pos.abs_diff(align_pos) as u128)
+        .sum()
+}
+
+// The triangular cost is (d² + d) / 2. Without the linear part, the optimum would be exactly at the
+// mean. The linear part can only shift the optimum by less than half a position, so it is enough to
+// check the integer positions around the mean.
+fn mean_alignment(positions: &[u64]) -> Result<u64, String> {
+    if positions.is_empty() {
+        return Ok(0);
+    }
+    let sum: u128 = positions.iter().map(|pos| *pos as u128).sum();
+    let mean = (sum / positions.len() as u128) as u64;
+
+    let best = (mean.saturating_sub(1)..=mean.saturating_add(2))
+        .filter_map(|align_pos| triangular_cost(positions, align_pos))
         .min()
-        .unwrap_or(0)
+        .ok_or_else(|| "the total fuel usage does not even fit into 128 bits".to_owned())?;
+    fuel_total(best)
+}
+
+// `None` if the total does not fit into u128
+fn triangular_cost(positions: &[u64], align_pos: u64) -> Option<u128> {
+    positions.iter().try_fold(0u128, |sum, pos| {
+        sum.checked_add(fuel_usage(pos.abs_diff(align_pos)))
+    })
 }
 
-fn fuel_usage(distance: u32) -> u32 {
-    ((distance + 1) * distance) / 2
+// the result is less than 2^127, even for the largest distance
+fn fuel_usage(distance: u64) -> u128 {
+    let distance = distance as u128;
+    distance * (distance + 1) / 2
 }
 
-fn parse(input: &str) -> Result<Vec<u32>, String> {
+fn fuel_total(total: u128) -> Result<u64, String> {
+    u64::try_from(total).map_err(|_| {
+        format!(
+            "the total fuel usage of {} does not fit into 64 bits",
+            total
+        )
+    })
+}
+
+fn parse(input: &str) -> Result<Vec<u64>, String> {
     input
         .split(',')
         .map(|s| {
             s.trim()
-                .parse::<u32>()
+                .parse::<u64>()
                 .map_err(|e| format!("Unable to parse position: {}", e))
         })
         .collect()
@@ -86,26 +270,193 @@ mod test {
     use super::*;
 
     #[test]
-    fn brute_force_alignment_works_for_example() {
+    fn median_alignment_works_for_example() {
+        // given
+        let positions = parse("16,1,2,0,4,2,7,1,2,14\n").expect("Expected successful parsing");
+
+        // when
+        let fuel = median_alignment(&positions);
+
+        // then
+        assert_eq!(fuel, Ok(37));
+    }
+
+    #[test]
+    fn mean_alignment_works_for_example() {
         // given
         let positions = parse("16,1,2,0,4,2,7,1,2,14\n").expect("Expected successful parsing");
 
         // when
-        let fuel = brute_force_alignment(&positions);
+        let fuel = mean_alignment(&positions);
 
         // then
-        assert_eq!(fuel, 37);
+        assert_eq!(fuel, Ok(168));
     }
 
     #[test]
-    fn brute_force_squared_cost_alignment_works_for_example() {
+    fn alignments_match_brute_force() {
+        // given
+        let inputs: [&[u64]; 4] = [&[0, 100], &[3, 3, 3, 50], &[1, 2, 90, 91, 92], &[7]];
+
+        for positions in inputs {
+            let max = positions.iter().max().copied().unwrap_or(0);
+
+            // when
+            let linear = median_alignment(positions);
+            let triangular = mean_alignment(positions);
+
+            // then
+            let brute_force_linear = (0..=max).map(|p| linear_cost(positions, p)).min();
+            let brute_force_triangular = (0..=max)
+                .filter_map(|p| triangular_cost(positions, p))
+                .min();
+            assert_eq!(linear.map(|l| l as u128).ok(), brute_force_linear);
+            assert_eq!(triangular.map(|t| t as u128).ok(), brute_force_triangular);
+        }
+    }
+
+    #[test]
+    fn alignments_work_for_sparse_huge_positions() {
+        // given
+        let positions = parse("0,4000000000,4000000002\n").expect("Expected successful parsing");
+
+        // when
+        let linear = median_alignment(&positions);
+        let triangular = mean_alignment(&positions);
+
+        // then
+        assert_eq!(linear, Ok(4000000002));
+        // optimum is at 2666666667
+        assert_eq!(triangular, Ok(5333333338666666669));
+    }
+
+    #[test]
+    fn alignments_fail_beyond_u64() {
+        // given
+        let two_crabs = parse("0,10000000000\n").expect("Expected successful parsing");
+        let three_crabs =
+            parse("0,20000000000,20000000000\n").expect("Expected successful parsing");
+
+        // when
+        let linear = median_alignment(&two_crabs);
+        let triangular = mean_alignment(&two_crabs);
+        let triangular_three = mean_alignment(&three_crabs);
+
+        // then
+        assert_eq!(linear, Ok(10000000000));
+        // 2 * (5 * 10^9) * (5 * 10^9 + 1) / 2
+        assert_eq!(
+            triangular,
+            Err(
+                "the total fuel usage of 25000000005000000000 does not fit into 64 bits".to_owned()
+            )
+        );
+        assert!(triangular_three.is_err());
+    }
+
+    #[test]
+    fn optimise_alignment_works_for_example() {
         // given
         let positions = parse("16,1,2,0,4,2,7,1,2,14\n").expect("Expected successful parsing");
 
         // when
-        let fuel = brute_force_squared_cost_alignment(&positions);
+        let linear = optimise_alignment(&positions, &Linear);
+        let triangular = optimise_alignment(&positions, &Triangular);
+
+        // then
+        assert_eq!(linear, Ok(Some((2, 37))));
+        assert_eq!(triangular, Ok(Some((5, 168))));
+    }
+
+    #[test]
+    fn optimise_alignment_works_for_quadratic_and_weighted_cost() {
+        // given
+        let positions = parse("0,10\n").expect("Expected successful parsing");
+        let weighted = Weighted {
+            weights: vec![1, 4],
+            inner: Quadratic,
+        };
+
+        // when
+        let quadratic = optimise_alignment(&positions, &Quadratic);
+        let weighted = optimise_alignment(&positions, &weighted);
+
+        // then
+        assert_eq!(quadratic, Ok(Some((5, 50))));
+        // 8² + 4 * 2² = 80
+        assert_eq!(weighted, Ok(Some((8, 80))));
+    }
+
+    struct Capped;
+
+    impl CostFunction for Capped {
+        fn cost(&self, _: usize, distance: u64) -> Option<u128> {
+            Some(distance.min(3) as u128)
+        }
+
+        fn is_convex(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn optimise_alignment_scans_non_convex_costs() {
+        // given
+        let positions = parse("0,1,2,50,100\n").expect("Expected successful parsing");
+
+        // when
+        let best = optimise_alignment(&positions, &Capped);
+
+        // then
+        assert_eq!(best, Ok(Some((1, 8))));
+    }
+
+    #[test]
+    fn optimise_alignment_returns_none_without_crabs() {
+        assert_eq!(optimise_alignment(&[], &Linear), Ok(None));
+    }
+
+    #[test]
+    fn optimise_alignment_works_beyond_u64() {
+        // given
+        let positions = parse("0,20000000000,20000000000\n").expect("Expected successful parsing");
+
+        // when
+        let triangular = optimise_alignment(&positions, &Triangular);
+        let quadratic = optimise_alignment(&positions, &Quadratic);
+
+        // then
+        let mean = 40000000000 / 3;
+        let expected_triangular = [mean, mean + 1]
+            .into_iter()
+            .map(|p| {
+                (
+                    p,
+                    total_cost(&positions, &Triangular, p).expect("Expected a total"),
+                )
+            })
+            .min_by_key(|(_, cost)| *cost);
+        assert_eq!(triangular, Ok(expected_triangular));
+        let (quadratic_position, quadratic_cost) = quadratic
+            .expect("Expected no overflow")
+            .expect("Expected an optimum");
+        assert_eq!(quadratic_position, 13333333333);
+        assert!(quadratic_cost > u64::MAX as u128);
+    }
+
+    #[test]
+    fn optimise_alignment_fails_beyond_u128() {
+        // given
+        let positions = parse("0,18446744073709551615\n").expect("Expected successful parsing");
+        let weighted = Weighted {
+            weights: vec![u64::MAX, u64::MAX],
+            inner: Quadratic,
+        };
+
+        // when
+        let best = optimise_alignment(&positions, &weighted);
 
         // then
-        assert_eq!(fuel, 168);
+        assert!(best.is_err());
     }
 }
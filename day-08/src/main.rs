@@ -1,12 +1,19 @@
 use std::env;
+use std::fmt;
 use std::fs::read_to_string;
 use std::path::Path;
 
 fn main() -> Result<(), String> {
+    match env::args().nth(1).as_deref() {
+        Some("--generate") => return print_fixtures(),
+        Some("--render") => return print_rendered_values(),
+        _ => (),
+    }
+
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
 
     let input = parse(&content)?;
 
@@ -16,12 +23,82 @@ fn main() -> Result<(), String> {
         trivial_values
     );
 
-    if let Some(number) = sum_numbers(&input) {
+    // optional second file: a font with the ten digit patterns in standard segment names
+    if let Some(font) = read_font(env::args().nth(2))? {
+        let mut sum: u32 = 0;
+        for (i, (patterns, values)) in input.iter().enumerate() {
+            match decode_number(patterns, values, &font) {
+                Ok(number) => sum += number,
+                Err(e) => println!("Unable to decode line {}: {}", i + 1, e),
+            }
+        }
+        println!("Sum of decodable numbers is {}", sum);
+    } else if let Some(number) = sum_numbers(&input) {
         println!("Sum of numbers is {}", number);
     } else {
         println!("Unable to find correct patterns for all values");
+        for (i, (patterns, values)) in input.iter().enumerate() {
+            if let Err(e) = decode_number(patterns, values, &STANDARD_FONT) {
+                println!("line {}: {}", i + 1, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_font(filename: Option<String>) -> Result<Option<Font>, String> {
+    match filename {
+        Some(filename) => Ok(Some(parse_font(
+            &read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+// usage: --generate <number of lines> [seed]
+fn print_fixtures() -> Result<(), String> {
+    let n_lines: usize = env::args()
+        .nth(2)
+        .ok_or_else(|| "No number of lines given.".to_owned())?
+        .parse()
+        .map_err(|e| format!("Unable to parse number of lines: {}", e))?;
+    let mut rng = XorShift(match env::args().nth(3) {
+        Some(seed) => seed
+            .parse()
+            .map_err(|e| format!("Unable to parse seed: {}", e))?,
+        None => 2021,
+    });
+    for _ in 0..n_lines {
+        let number = (rng.next() % 10000) as u32;
+        let wiring = random_wiring(&mut rng);
+        println!(
+            "{}",
+            encode_line(number, &wiring, &STANDARD_FONT, &mut rng)?
+        );
     }
+    Ok(())
+}
 
+// usage: --render <file> [font file]
+fn print_rendered_values() -> Result<(), String> {
+    let filename = env::args()
+        .nth(2)
+        .ok_or_else(|| "No file name given.".to_owned())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
+    let input = parse(&content)?;
+    let font = read_font(env::args().nth(3))?.unwrap_or(STANDARD_FONT);
+
+    for (i, (patterns, values)) in input.iter().enumerate() {
+        match solve_wiring(patterns, &font) {
+            Ok(wiring) => {
+                let segments: Vec<u8> = values.iter().map(|v| apply_wiring(&wiring, *v)).collect();
+                print!("{}", render_segments(&segments));
+            }
+            Err(e) => println!("Unable to decode line {}: {}", i + 1, e),
+        }
+        println!();
+    }
     Ok(())
 }
 
@@ -52,7 +129,7 @@ fn sum_numbers(input: &[(Patterns, Values)]) -> Option<u32> {
     input
         .iter()
         .map(|(patterns, values)| determine_number(patterns, values))
-        .fold(Some(0), |sum, n| Some(sum? + n?))
+        .try_fold(0, |sum, n| Some(sum + n?))
 }
 
 fn determine_number(patterns: &Patterns, values: &Values) -> Option<u32> {
@@ -103,6 +180,254 @@ fn determine_number(patterns: &Patterns, values: &Values) -> Option<u32> {
     Some(result)
 }
 
+// segment patterns of the digits 0 to 9, using the same bits as `parse_code`
+type Font = [u8; 10];
+
+const STANDARD_FONT: Font = [
+    0b1110111, 0b0100100, 0b1011101, 0b1101101, 0b0101110, 0b1101011, 0b1111011, 0b0100101,
+    0b1111111, 0b1101111,
+];
+
+// `wiring[wire]` is the segment that is lit when the wire is active
+type Wiring = [u8; 7];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum WiringError {
+    // no wiring turns all patterns into digits. Lists the patterns that the best wiring could not
+    // turn into digits.
+    Inconsistent { conflicting_patterns: Vec<u8> },
+    // more than one wiring turns all patterns into digits
+    Ambiguous { wirings: Vec<Wiring> },
+    // the value is not a digit with the wiring
+    UnknownValue(u8),
+}
+
+impl fmt::Display for WiringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiringError::Inconsistent {
+                conflicting_patterns,
+            } => {
+                let codes: Vec<String> = conflicting_patterns
+                    .iter()
+                    .map(|p| format_code(*p))
+                    .collect();
+                write!(
+                    f,
+                    "no wiring fits, conflicting patterns: {}",
+                    codes.join(" ")
+                )
+            }
+            WiringError::Ambiguous { wirings } => {
+                let wirings: Vec<String> = wirings.iter().map(format_wiring).collect();
+                write!(f, "ambiguous wirings: {}", wirings.join(", "))
+            }
+            WiringError::UnknownValue(value) => {
+                write!(f, "value '{}' is not a digit", format_code(*value))
+            }
+        }
+    }
+}
+
+fn apply_wiring(wiring: &Wiring, code: u8) -> u8 {
+    wiring
+        .iter()
+        .enumerate()
+        .filter(|(wire, _)| code & (1 << wire) != 0)
+        .fold(0, |segments, (_, segment)| segments | (1 << segment))
+}
+
+fn digit_for_segments(font: &Font, segments: u8) -> Option<u8> {
+    font.iter().position(|s| *s == segments).map(|d| d as u8)
+}
+
+fn all_wirings() -> Vec<Wiring> {
+    fn permute(wiring: &mut Wiring, k: usize, result: &mut Vec<Wiring>) {
+        if k == wiring.len() {
+            result.push(*wiring);
+            return;
+        }
+        for i in k..wiring.len() {
+            wiring.swap(k, i);
+            permute(wiring, k + 1, result);
+            wiring.swap(k, i);
+        }
+    }
+    let mut result: Vec<Wiring> = Vec::with_capacity(5040);
+    permute(&mut [0, 1, 2, 3, 4, 5, 6], 0, &mut result);
+    result
+}
+
+// Tries all 7! wirings. That sounds like a lot, but is still fast enough.
+fn solve_wiring(patterns: &[u8], font: &Font) -> Result<Wiring, WiringError> {
+    let mut fitting: Vec<Wiring> = Vec::with_capacity(1);
+    let mut best_conflicts: Option<Vec<u8>> = None;
+    for wiring in all_wirings() {
+        let conflicts: Vec<u8> = patterns
+            .iter()
+            .copied()
+            .filter(|p| digit_for_segments(font, apply_wiring(&wiring, *p)).is_none())
+            .collect();
+        if conflicts.is_empty() {
+            fitting.push(wiring);
+        } else if best_conflicts
+            .as_ref()
+            .map(|best| conflicts.len() < best.len())
+            .unwrap_or(true)
+        {
+            best_conflicts = Some(conflicts);
+        }
+    }
+    match fitting.len() {
+        0 => Err(WiringError::Inconsistent {
+            conflicting_patterns: best_conflicts.unwrap_or_default(),
+        }),
+        1 => Ok(fitting[0]),
+        _ => Err(WiringError::Ambiguous { wirings: fitting }),
+    }
+}
+
+fn decode_number(patterns: &Patterns, values: &Values, font: &Font) -> Result<u32, WiringError> {
+    let wiring = solve_wiring(patterns, font)?;
+    values.iter().try_fold(0u32, |result, value| {
+        let digit = digit_for_segments(font, apply_wiring(&wiring, *value))
+            .ok_or(WiringError::UnknownValue(*value))?;
+        Ok(result * 10 + digit as u32)
+    })
+}
+
+// the inverse of `apply_wiring`: which wires need to be active to light up the segments
+fn scramble_segments(wiring: &Wiring, segments: u8) -> u8 {
+    wiring
+        .iter()
+        .enumerate()
+        .filter(|(_, segment)| segments & (1 << **segment) != 0)
+        .fold(0, |code, (wire, _)| code | (1 << wire))
+}
+
+// Produces a line in the puzzle input format that shows the (zero padded) number on a display
+// with the given wiring. Like in the real input, the patterns come in random order.
+fn encode_line(
+    number: u32,
+    wiring: &Wiring,
+    font: &Font,
+    rng: &mut XorShift,
+) -> Result<String, String> {
+    if number >= 10000 {
+        return Err(format!("{} has more than four digits", number));
+    }
+    let mut patterns: Vec<String> = font
+        .iter()
+        .map(|segments| format_code(scramble_segments(wiring, *segments)))
+        .collect();
+    shuffle(&mut patterns, rng);
+    let values: Vec<String> = [1000, 100, 10, 1]
+        .iter()
+        .map(|place| {
+            let segments = font[(number / place % 10) as usize];
+            format_code(scramble_segments(wiring, segments))
+        })
+        .collect();
+    Ok(format!("{} | {}", patterns.join(" "), values.join(" ")))
+}
+
+// Renders digits side by side, three lines high:
+//  _     _
+// | |  | _|
+// |_|  ||_
+fn render_segments(digits: &[u8]) -> String {
+    let lit = |segments: u8, segment: u8, c: char| {
+        if segments & (1 << segment) != 0 {
+            c
+        } else {
+            ' '
+        }
+    };
+    let mut lines: [String; 3] = [String::new(), String::new(), String::new()];
+    for segments in digits.iter().copied() {
+        lines[0].push(' ');
+        lines[0].push(lit(segments, 0, '_'));
+        lines[0].push(' ');
+        lines[1].push(lit(segments, 1, '|'));
+        lines[1].push(lit(segments, 3, '_'));
+        lines[1].push(lit(segments, 2, '|'));
+        lines[2].push(lit(segments, 4, '|'));
+        lines[2].push(lit(segments, 6, '_'));
+        lines[2].push(lit(segments, 5, '|'));
+    }
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line.trim_end()))
+        .collect()
+}
+
+// Good enough to generate random test data, not good enough for anything else.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        // xorshift breaks down with a state of zero
+        if self.0 == 0 {
+            self.0 = 0x9e3779b97f4a7c15;
+        }
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// Fisher-Yates
+fn shuffle<T>(items: &mut [T], rng: &mut XorShift) {
+    for i in (1..items.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+fn random_wiring(rng: &mut XorShift) -> Wiring {
+    let mut wiring: Wiring = [0, 1, 2, 3, 4, 5, 6];
+    shuffle(&mut wiring, rng);
+    wiring
+}
+
+fn format_code(code: u8) -> String {
+    ('a'..='g')
+        .enumerate()
+        .filter(|(i, _)| code & (1 << i) != 0)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+// e.g. "a->d b->e …"
+fn format_wiring(wiring: &Wiring) -> String {
+    let mappings: Vec<String> = wiring
+        .iter()
+        .zip('a'..='g')
+        .map(|(segment, wire)| format!("{}->{}", wire, (b'a' + segment) as char))
+        .collect();
+    mappings.join(" ")
+}
+
+fn parse_font(input: &str) -> Result<Font, String> {
+    let mut font: Font = [0; 10];
+    let mut n_digits = 0;
+    for (i, code) in input.split_whitespace().enumerate() {
+        if i >= font.len() {
+            return Err("found more than 10 digits in font".to_owned());
+        }
+        font[i] = parse_code(code)?;
+        if font[..i].contains(&font[i]) {
+            return Err(format!("digit {} looks like another digit in font", i));
+        }
+        n_digits = i + 1;
+    }
+    if n_digits != font.len() {
+        return Err(format!("expected 10 digits in font, found {}", n_digits));
+    }
+    Ok(font)
+}
+
 fn parse(input: &str) -> Result<Vec<(Patterns, Values)>, String> {
     input.lines().map(parse_line).collect()
 }
@@ -218,4 +543,174 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         // then
         assert_eq!(sum, Some(61229));
     }
+
+    #[test]
+    fn solve_wiring_works_for_example() {
+        // given
+        let (patterns, _) = parse_line(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )
+        .expect("expected successful parsing");
+
+        // when
+        let wiring = solve_wiring(&patterns, &STANDARD_FONT);
+
+        // then
+        // from the puzzle description: d->a, e->b, a->c, f->d, g->e, b->f, c->g
+        assert_eq!(wiring, Ok([2, 5, 6, 0, 1, 3, 4]));
+    }
+
+    #[test]
+    fn decode_number_matches_determine_number() {
+        // given
+        let input = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+
+        for (patterns, values) in input {
+            // when
+            let decoded = decode_number(&patterns, &values, &STANDARD_FONT);
+
+            // then
+            assert_eq!(decoded.ok(), determine_number(&patterns, &values));
+        }
+    }
+
+    #[test]
+    fn solve_wiring_reports_conflicting_patterns() {
+        // given
+        // 'ab' and 'abcdefg' are fine, but no digit has only a single segment
+        let patterns = [
+            parse_code("ab").unwrap(),
+            parse_code("abcdefg").unwrap(),
+            parse_code("a").unwrap(),
+        ];
+
+        // when
+        let result = solve_wiring(&patterns, &STANDARD_FONT);
+
+        // then
+        assert_eq!(
+            result,
+            Err(WiringError::Inconsistent {
+                conflicting_patterns: vec![parse_code("a").unwrap()]
+            })
+        );
+    }
+
+    #[test]
+    fn solve_wiring_reports_ambiguous_wirings() {
+        // given
+        let patterns = [parse_code("ab").unwrap(), parse_code("abcdefg").unwrap()];
+
+        // when
+        let result = solve_wiring(&patterns, &STANDARD_FONT);
+
+        // then
+        match result {
+            Err(WiringError::Ambiguous { wirings }) => assert_eq!(wirings.len(), 2 * 120),
+            other => panic!("Expected ambiguous wirings, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_number_works_with_alternative_font() {
+        // given
+        // 7 with the top left segment (like on some calculators)
+        let font = parse_font("abcefg cf acdeg acdfg bcdf abdfg abdefg abcf abcdefg abcdfg")
+            .expect("Expected successful parsing");
+        let wiring: Wiring = [2, 5, 6, 0, 1, 3, 4];
+        let (patterns, values) = parse_line(
+            &encode_line(7109, &wiring, &font, &mut XorShift(7))
+                .expect("Expected successful encoding"),
+        )
+        .expect("Expected successful parsing");
+
+        // when
+        let number = decode_number(&patterns, &values, &font);
+
+        // then
+        assert_eq!(number, Ok(7109));
+    }
+
+    #[test]
+    fn parse_font_rejects_missing_digits() {
+        // when
+        let font = parse_font("abcefg cf acdeg acdfg bcdf abdfg abdefg acf abcdefg");
+
+        // then
+        assert_eq!(font, Err("expected 10 digits in font, found 9".to_owned()));
+    }
+
+    #[test]
+    fn encode_line_works_for_example() {
+        // given
+        let (patterns, values) = parse_line(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )
+        .expect("expected successful parsing");
+        let wiring = solve_wiring(&patterns, &STANDARD_FONT).expect("Expected a wiring");
+
+        // when
+        let line = encode_line(5353, &wiring, &STANDARD_FONT, &mut XorShift(7))
+            .expect("Expected successful encoding");
+
+        // then
+        let (encoded_patterns, encoded_values) =
+            parse_line(&line).expect("Expected successful parsing");
+        let mut expected_patterns = patterns;
+        expected_patterns.sort_unstable();
+        let mut encoded_patterns = encoded_patterns;
+        encoded_patterns.sort_unstable();
+        assert_eq!(encoded_patterns, expected_patterns);
+        assert_eq!(encoded_values, values);
+    }
+
+    #[test]
+    fn encode_line_rejects_numbers_with_five_digits() {
+        assert!(encode_line(
+            10000,
+            &[0, 1, 2, 3, 4, 5, 6],
+            &STANDARD_FONT,
+            &mut XorShift(7)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn generated_fixtures_decode_to_their_numbers() {
+        // given
+        let mut rng = XorShift(42);
+        let mut n_shuffled = 0;
+
+        for _ in 0..20 {
+            let number = (rng.next() % 10000) as u32;
+            let wiring = random_wiring(&mut rng);
+            let line = encode_line(number, &wiring, &STANDARD_FONT, &mut rng)
+                .expect("Expected successful encoding");
+
+            // when
+            let (patterns, values) = parse_line(&line).expect("Expected successful parsing");
+
+            // then
+            assert_eq!(determine_number(&patterns, &values), Some(number));
+            assert_eq!(solve_wiring(&patterns, &STANDARD_FONT), Ok(wiring));
+            let in_digit_order = STANDARD_FONT.map(|segments| scramble_segments(&wiring, segments));
+            if patterns != in_digit_order {
+                n_shuffled += 1;
+            }
+        }
+        // the patterns must not give away which digit they are
+        assert!(n_shuffled > 15);
+    }
+
+    #[test]
+    fn render_segments_works_for_all_digits() {
+        // when
+        let rendered = render_segments(&STANDARD_FONT);
+
+        // then
+        assert_eq!(
+            rendered,
+            " _     _  _     _  _  _  _  _\n| |  | _| _||_||_ |_   ||_||_|\n|_|  ||_  _|  | _||_|  ||_| _|\n"
+        );
+    }
 }
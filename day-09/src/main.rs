@@ -1,23 +1,74 @@
 use std::env;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write, File};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+
+    // the streaming mode only needs a few rows in memory, but can only find low points
+    if env::args().nth(2).as_deref() == Some("--stream") {
+        let file = File::open(Path::new(&filename)).map_err(|e| e.to_string())?;
+        let mut n_low_points: usize = 0;
+        let mut risk: u32 = 0;
+        stream_low_points(BufReader::new(file), |_, _, value| {
+            n_low_points += 1;
+            risk += value as u32 + 1;
+        })?;
+        println!(
+            "Sum of risk levels on {} low points is {}",
+            n_low_points, risk
+        );
+        return Ok(());
+    }
+
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
     let height_map = parse(&content)?;
 
     let risk = get_low_points_risk_level(&height_map);
     println!("Sum of risk levels on low points is {}", risk);
 
-    let basin_product = fill_basins(&height_map)?;
+    let mut rules = BasinRules::default();
+    let mut ansi = false;
+    let mut ppm_filename: Option<String> = None;
+    for arg in env::args().skip(2) {
+        if arg == "--ansi" {
+            ansi = true;
+        } else if let Some(filename) = arg.strip_prefix("--ppm=") {
+            ppm_filename = Some(filename.to_owned());
+        } else if let Some(ridge_height) = arg.strip_prefix("--ridge=") {
+            rules.ridge_height = ridge_height
+                .parse()
+                .map_err(|e| format!("Unable to parse ridge height: {}", e))?;
+        } else if arg == "--moore" {
+            rules.neighbourhood = Neighbourhood::Moore;
+        } else if arg == "--drainage" {
+            rules.mode = BasinMode::Drainage;
+        } else {
+            return Err(format!("Unknown option: {}", arg));
+        }
+    }
+
+    let basin_product = fill_basins(&height_map, &rules)?;
     println!(
         "Product of the size of the three largest basins: {}",
         basin_product
     );
 
+    let basins = label_basins(&height_map, &rules);
+    if ansi {
+        print!("{}", render_basins_ansi(&height_map, &basins));
+    }
+    if let Some(ppm_filename) = ppm_filename {
+        write(
+            Path::new(&ppm_filename),
+            render_basins_ppm(&height_map, &basins),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
     Ok(())
 }
 
@@ -45,7 +96,7 @@ fn bottom(i: usize, height_map: &HeightMap) -> Option<(usize, u8)> {
 }
 
 fn left(i: usize, height_map: &HeightMap) -> Option<(usize, u8)> {
-    if i % height_map.width > 0 {
+    if !i.is_multiple_of(height_map.width) {
         Some((i - 1, height_map.values[i - 1]))
     } else {
         None
@@ -60,43 +111,173 @@ fn right(i: usize, height_map: &HeightMap) -> Option<(usize, u8)> {
     }
 }
 
-fn fill_basins(height_map: &HeightMap) -> Result<u32, String> {
-    // turns out a simple Vec<bool> would be sufficient, but I leave this in in case I want to make
-    // a visualization of the basins
-    let mut basin_map: Vec<Option<usize>> = vec![None; height_map.values.len()];
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Neighbourhood {
+    // only horizontal and vertical neighbours
+    VonNeumann,
+    // diagonal neighbours as well
+    Moore,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BasinMode {
+    // a basin is everything reachable from its low point without crossing a ridge
+    FloodFill,
+    // every cell belongs to the low point it flows down to, always taking the lowest neighbour
+    Drainage,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct BasinRules {
+    // cells of this height or higher do not belong to any basin
+    ridge_height: u8,
+    neighbourhood: Neighbourhood,
+    mode: BasinMode,
+}
+
+impl Default for BasinRules {
+    fn default() -> BasinRules {
+        BasinRules {
+            ridge_height: 9,
+            neighbourhood: Neighbourhood::VonNeumann,
+            mode: BasinMode::FloodFill,
+        }
+    }
+}
+
+fn neighbours(
+    i: usize,
+    height_map: &HeightMap,
+    neighbourhood: Neighbourhood,
+) -> impl Iterator<Item = (usize, u8)> {
+    let top = top(i, height_map);
+    let bottom = bottom(i, height_map);
+    let diagonals = match neighbourhood {
+        Neighbourhood::VonNeumann => [None; 4],
+        Neighbourhood::Moore => [
+            top.and_then(|(t, _)| left(t, height_map)),
+            top.and_then(|(t, _)| right(t, height_map)),
+            bottom.and_then(|(b, _)| left(b, height_map)),
+            bottom.and_then(|(b, _)| right(b, height_map)),
+        ],
+    };
+    [top, bottom, left(i, height_map), right(i, height_map)]
+        .into_iter()
+        .chain(diagonals)
+        .flatten()
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Basins {
+    // basin index for every cell, `None` if the cell does not belong to any basin
+    labels: Vec<Option<usize>>,
+    // size and low point of every basin
+    sizes: Vec<u32>,
+    low_points: Vec<usize>,
+    // all cells at or above the ridge height of the rules, which belong to no basin
+    ridges: Vec<usize>,
+}
+
+fn label_basins(height_map: &HeightMap, rules: &BasinRules) -> Basins {
+    let (labels, low_points) = match rules.mode {
+        BasinMode::FloodFill => flood_fill_basins(height_map, rules),
+        BasinMode::Drainage => drain_basins(height_map, rules),
+    };
+    let mut sizes: Vec<u32> = vec![0; low_points.len()];
+    for basin in labels.iter().flatten() {
+        sizes[*basin] += 1;
+    }
+    let ridges: Vec<usize> = height_map
+        .values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v >= rules.ridge_height)
+        .map(|(i, _)| i)
+        .collect();
+
+    Basins {
+        labels,
+        sizes,
+        low_points,
+        ridges,
+    }
+}
+
+type Labelling = (Vec<Option<usize>>, Vec<usize>);
+
+fn flood_fill_basins(height_map: &HeightMap, rules: &BasinRules) -> Labelling {
+    let mut labels: Vec<Option<usize>> = vec![None; height_map.values.len()];
     let mut stack: Vec<usize> = Vec::with_capacity(height_map.values.len());
-    let mut basin_sizes: Vec<u32> = Vec::with_capacity(height_map.values.len());
+    let mut low_points: Vec<usize> = Vec::with_capacity(height_map.values.len());
 
     for i in 0..height_map.values.len() {
-        if basin_map[i].is_some() {
+        if labels[i].is_some() || height_map.values[i] >= rules.ridge_height {
             continue;
         }
-        if is_low_point(i, height_map) {
-            let mut basin_size: u32 = 0;
+        if is_low_point_in(i, height_map, rules.neighbourhood) {
             stack.push(i);
             while let Some(index) = stack.pop() {
-                if basin_map[index].is_some() {
+                if labels[index].is_some() {
                     continue;
                 }
-                basin_size += 1;
-                basin_map[index] = Some(basin_sizes.len());
-                for (neighbour_i, neighbour_v) in [
-                    top(index, height_map),
-                    bottom(index, height_map),
-                    left(index, height_map),
-                    right(index, height_map),
-                ]
-                .into_iter()
-                .flatten()
+                labels[index] = Some(low_points.len());
+                for (neighbour_i, neighbour_v) in neighbours(index, height_map, rules.neighbourhood)
                 {
-                    if neighbour_v < 9 {
+                    if neighbour_v < rules.ridge_height {
                         stack.push(neighbour_i);
                     }
                 }
             }
-            basin_sizes.push(basin_size);
+            low_points.push(i);
         }
     }
+    (labels, low_points)
+}
+
+fn drain_basins(height_map: &HeightMap, rules: &BasinRules) -> Labelling {
+    let low_points: Vec<usize> = (0..height_map.values.len())
+        .filter(|i| {
+            height_map.values[*i] < rules.ridge_height
+                && is_low_point_in(*i, height_map, rules.neighbourhood)
+        })
+        .collect();
+    let mut labels: Vec<Option<usize>> = vec![None; height_map.values.len()];
+    // cells that are already done, even if they do not drain into any low point (e.g. plateaus)
+    let mut visited: Vec<bool> = vec![false; height_map.values.len()];
+    for (basin, low_point) in low_points.iter().enumerate() {
+        labels[*low_point] = Some(basin);
+        visited[*low_point] = true;
+    }
+
+    let mut path: Vec<usize> = Vec::with_capacity(height_map.values.len());
+    for start in 0..height_map.values.len() {
+        if visited[start] || height_map.values[start] >= rules.ridge_height {
+            continue;
+        }
+        // follow the steepest descent until we reach a cell we already know
+        let mut current = start;
+        while !visited[current] {
+            path.push(current);
+            visited[current] = true;
+            let value = height_map.values[current];
+            match neighbours(current, height_map, rules.neighbourhood)
+                .filter(|(_, v)| *v < value)
+                .min_by_key(|(_, v)| *v)
+            {
+                Some((lower, _)) => current = lower,
+                None => break,
+            }
+        }
+        let basin = labels[current];
+        for cell in path.drain(..) {
+            labels[cell] = basin;
+        }
+    }
+    (labels, low_points)
+}
+
+fn fill_basins(height_map: &HeightMap, rules: &BasinRules) -> Result<u32, String> {
+    let mut basin_sizes = label_basins(height_map, rules).sizes;
     if basin_sizes.len() < 3 {
         return Err(format!(
             "Found only {} basins, need at least 3",
@@ -109,20 +290,75 @@ fn fill_basins(height_map: &HeightMap) -> Result<u32, String> {
         .product())
 }
 
+// Spreads the hues by the golden angle so that neighbouring basins (which usually have close
+// indices) get clearly different colours.
+fn basin_colour(basin: usize) -> (u8, u8, u8) {
+    let hue = (basin as f64 * 137.507_764) % 360.0;
+    let x = 1.0 - ((hue / 60.0) % 2.0 - 1.0).abs();
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    // not too bright, so that the height digits are still readable on top of it
+    let scale = |c: f64| (64.0 + c * 160.0) as u8;
+    (scale(r), scale(g), scale(b))
+}
+
+fn cell_colour(i: usize, basins: &Basins) -> (u8, u8, u8) {
+    match basins.labels[i] {
+        Some(basin) if basins.low_points[basin] == i => (255, 255, 255),
+        Some(basin) => basin_colour(basin),
+        None => (0, 0, 0),
+    }
+}
+
+// the height map with coloured backgrounds for the basins, low points are white
+fn render_basins_ansi(height_map: &HeightMap, basins: &Basins) -> String {
+    let mut result = String::with_capacity(height_map.values.len() * 20);
+    for (i, value) in height_map.values.iter().enumerate() {
+        let (r, g, b) = cell_colour(i, basins);
+        let foreground = if basins.labels[i].is_some() { 30 } else { 90 };
+        result.push_str(&format!(
+            "\x1b[{};48;2;{};{};{}m{}",
+            foreground, r, g, b, value
+        ));
+        if (i + 1).is_multiple_of(height_map.width) {
+            result.push_str("\x1b[0m\n");
+        }
+    }
+    result
+}
+
+// plain text PPM, one pixel per cell
+fn render_basins_ppm(height_map: &HeightMap, basins: &Basins) -> String {
+    let mut result = format!("P3\n{} {}\n255\n", height_map.width, height_map.height);
+    for row in 0..height_map.height {
+        let pixels: Vec<String> = (0..height_map.width)
+            .map(|column| {
+                let (r, g, b) = cell_colour(row * height_map.width + column, basins);
+                format!("{} {} {}", r, g, b)
+            })
+            .collect();
+        result.push_str(&pixels.join(" "));
+        result.push('\n');
+    }
+    result
+}
+
 fn is_low_point(i: usize, height_map: &HeightMap) -> bool {
+    is_low_point_in(i, height_map, Neighbourhood::VonNeumann)
+}
+
+fn is_low_point_in(i: usize, height_map: &HeightMap, neighbourhood: Neighbourhood) -> bool {
     if i >= height_map.values.len() {
         false
     } else {
         let value = height_map.values[i];
-        [
-            top(i, height_map),
-            bottom(i, height_map),
-            left(i, height_map),
-            right(i, height_map),
-        ]
-        .into_iter()
-        .flatten()
-        .all(|(_, v)| v > value)
+        neighbours(i, height_map, neighbourhood).all(|(_, v)| v > value)
     }
 }
 
@@ -137,6 +373,83 @@ fn get_low_points_risk_level(height_map: &HeightMap) -> u32 {
         .sum()
 }
 
+// Finds the same low points as `is_low_point`, but reads the height map row by row and only keeps
+// three rows in memory. Calls `on_low_point` with row, column and height of each low point.
+fn stream_low_points<R: BufRead>(
+    reader: R,
+    mut on_low_point: impl FnMut(usize, usize, u8),
+) -> Result<(), String> {
+    let mut above: Option<Vec<u8>> = None;
+    let mut current: Option<Vec<u8>> = None;
+    let mut row: usize = 0;
+
+    let mut lines = reader.lines();
+    loop {
+        let below: Option<Vec<u8>> = match lines.next() {
+            Some(line) => {
+                let line = line.map_err(|e| e.to_string())?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                Some(parse_row(line.trim())?)
+            }
+            None => None,
+        };
+        if let (Some(current), Some(below)) = (&current, &below) {
+            if current.len() != below.len() {
+                return Err(format!(
+                    "row {} has width {}, expected {}",
+                    // `row` is the zero-based index of the current row, `below` is the one after it
+                    row + 2,
+                    below.len(),
+                    current.len()
+                ));
+            }
+        }
+        if let Some(current) = &current {
+            for (column, value) in current.iter().copied().enumerate() {
+                if is_low_point_in_window(column, above.as_deref(), current, below.as_deref()) {
+                    on_low_point(row, column, value);
+                }
+            }
+            row += 1;
+        }
+        if below.is_none() {
+            return Ok(());
+        }
+        above = current;
+        current = below;
+    }
+}
+
+fn is_low_point_in_window(
+    column: usize,
+    above: Option<&[u8]>,
+    row: &[u8],
+    below: Option<&[u8]>,
+) -> bool {
+    let value = row[column];
+    [
+        above.map(|a| a[column]),
+        below.map(|b| b[column]),
+        column.checked_sub(1).map(|c| row[c]),
+        row.get(column + 1).copied(),
+    ]
+    .into_iter()
+    .flatten()
+    .all(|v| v > value)
+}
+
+fn parse_row(line: &str) -> Result<Vec<u8>, String> {
+    line.chars()
+        .map(|c| {
+            c.to_digit(10)
+                .map(|d| d as u8)
+                .ok_or_else(|| format!("unknown digit: {}", c))
+        })
+        .collect()
+}
+
 fn parse(input: &str) -> Result<HeightMap, String> {
     let values: Vec<u8> = input
         .chars()
@@ -153,7 +466,7 @@ fn parse(input: &str) -> Result<HeightMap, String> {
         .map(|l| l.len())
         .next()
         .ok_or_else(|| "expected at least one line".to_owned())?;
-    if values.len() % width != 0 {
+    if !values.len().is_multiple_of(width) {
         return Err(format!(
             "total size {} is not dividable by width {}",
             values.len(),
@@ -207,12 +520,127 @@ mod test {
         let height_map = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
 
         // when
-        let result = fill_basins(&height_map);
+        let result = fill_basins(&height_map, &BasinRules::default());
 
         // then
         assert_eq!(result, Ok(1134));
     }
 
+    #[test]
+    fn label_basins_works_for_example() {
+        // given
+        let height_map = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+
+        // when
+        let basins = label_basins(&height_map, &BasinRules::default());
+
+        // then
+        assert_eq!(basins.sizes, vec![3, 9, 14, 9]);
+        assert_eq!(basins.low_points, vec![1, 9, 22, 46]);
+        assert_eq!(
+            basins.ridges.len() + basins.sizes.iter().sum::<u32>() as usize,
+            height_map.values.len()
+        );
+        for (basin, low_point) in basins.low_points.iter().enumerate() {
+            assert_eq!(basins.labels[*low_point], Some(basin));
+        }
+        for ridge in &basins.ridges {
+            assert_eq!(basins.labels[*ridge], None);
+        }
+    }
+
+    #[test]
+    fn label_basins_works_for_lower_ridge_height() {
+        // given
+        let height_map = parse("0150\n1151\n5555\n").expect("Expected successful parsing");
+        let rules = BasinRules {
+            ridge_height: 5,
+            ..BasinRules::default()
+        };
+
+        // when
+        let basins = label_basins(&height_map, &rules);
+
+        // then
+        assert_eq!(basins.low_points, vec![0, 3]);
+        assert_eq!(basins.sizes, vec![4, 2]);
+        assert_eq!(basins.ridges, vec![2, 6, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn label_basins_works_for_moore_neighbourhood() {
+        // given
+        let height_map = parse("192\n909\n299\n").expect("Expected successful parsing");
+        let von_neumann = BasinRules::default();
+        let moore = BasinRules {
+            neighbourhood: Neighbourhood::Moore,
+            ..BasinRules::default()
+        };
+
+        // when
+        let separate = label_basins(&height_map, &von_neumann);
+        let connected = label_basins(&height_map, &moore);
+
+        // then
+        assert_eq!(separate.sizes, vec![1, 1, 1, 1]);
+        assert_eq!(connected.low_points, vec![4]);
+        assert_eq!(connected.sizes, vec![4]);
+    }
+
+    #[test]
+    fn label_basins_drains_to_lowest_neighbour() {
+        // given
+        let height_map = parse("05310\n").expect("Expected successful parsing");
+        let flood_fill = BasinRules::default();
+        let drainage = BasinRules {
+            mode: BasinMode::Drainage,
+            ..BasinRules::default()
+        };
+
+        // when
+        let flooded = label_basins(&height_map, &flood_fill);
+        let drained = label_basins(&height_map, &drainage);
+
+        // then
+        // without ridges, flood fill puts everything into the first basin
+        assert_eq!(flooded.sizes, vec![5]);
+        assert_eq!(
+            drained.labels,
+            vec![Some(0), Some(0), Some(1), Some(1), Some(1)]
+        );
+        assert_eq!(drained.sizes, vec![2, 3]);
+    }
+
+    #[test]
+    fn drainage_ignores_plateaus_without_low_point() {
+        // given
+        let height_map = parse("31130\n").expect("Expected successful parsing");
+        let rules = BasinRules {
+            mode: BasinMode::Drainage,
+            ..BasinRules::default()
+        };
+
+        // when
+        let basins = label_basins(&height_map, &rules);
+
+        // then
+        // the two 1s are not low points, because they are not lower than each other
+        assert_eq!(basins.labels, vec![None, None, None, Some(0), Some(0)]);
+    }
+
+    #[test]
+    fn render_basins_ppm_works_for_small_map() {
+        // given
+        let height_map = parse("091\n").expect("Expected successful parsing");
+        let basins = label_basins(&height_map, &BasinRules::default());
+
+        // when
+        let ppm = render_basins_ppm(&height_map, &basins);
+
+        // then
+        assert_eq!(ppm, "P3\n3 1\n255\n255 255 255 0 0 0 255 255 255\n");
+    }
+
     #[test]
     fn get_low_points_risk_level_works_for_example() {
         // given
@@ -288,4 +716,52 @@ mod test {
         // then
         assert_eq!(risk, 18);
     }
+
+    fn streaming_risk_level(input: &str) -> Result<u32, String> {
+        let mut risk: u32 = 0;
+        stream_low_points(input.as_bytes(), |_, _, value| risk += value as u32 + 1)?;
+        Ok(risk)
+    }
+
+    #[test]
+    fn stream_low_points_matches_get_low_points_risk_level() {
+        for input in [
+            EXAMPLE_INPUT,
+            "19191\n",
+            "1\n9\n1\n9\n1\n",
+            "19191\n99999\n19191\n99999\n19191\n",
+        ] {
+            // given
+            let height_map = parse(input).expect("Expected successful parsing");
+
+            // when
+            let risk = streaming_risk_level(input);
+
+            // then
+            assert_eq!(risk, Ok(get_low_points_risk_level(&height_map)));
+        }
+    }
+
+    #[test]
+    fn stream_low_points_reports_positions() {
+        // given
+        let mut low_points: Vec<(usize, usize, u8)> = Vec::new();
+
+        // when
+        let result = stream_low_points(EXAMPLE_INPUT.as_bytes(), |row, column, value| {
+            low_points.push((row, column, value))
+        });
+
+        // then
+        assert_eq!(result, Ok(()));
+        assert_eq!(low_points, vec![(0, 1, 1), (0, 9, 0), (2, 2, 5), (4, 6, 5)]);
+    }
+
+    #[test]
+    fn stream_low_points_rejects_ragged_rows() {
+        assert_eq!(
+            streaming_risk_level("123\n45\n"),
+            Err("row 2 has width 2, expected 3".to_owned())
+        );
+    }
 }
@@ -1,4 +1,6 @@
+use std::collections::{HashSet, VecDeque};
 use std::env;
+use std::fmt;
 use std::fs::read_to_string;
 use std::path::Path;
 
@@ -6,70 +8,424 @@ fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
     let lines: Vec<&str> = content.lines().collect();
 
-    let score = corruption_score(&lines);
+    let mut grammar = Grammar::standard();
+    let mut diagnose = false;
+    let mut fix = false;
+    for arg in env::args().skip(2) {
+        if let Some(grammar_filename) = arg.strip_prefix("--grammar=") {
+            grammar = parse_grammar(
+                &read_to_string(Path::new(grammar_filename)).map_err(|e| e.to_string())?,
+            )?;
+        } else if arg == "--diagnose" {
+            diagnose = true;
+        } else if arg == "--repair" {
+            fix = true;
+        } else {
+            return Err(format!("Unknown option: {}", arg));
+        }
+    }
+
+    let score = corruption_score(&lines, &grammar);
     println!("The corruption score is {}", score);
 
-    let completion = completion_score(&lines);
+    let completion = completion_score(&lines, &grammar);
     println!("The completion score is {}", completion);
 
+    if diagnose {
+        for (i, line) in lines.iter().enumerate() {
+            println!("{}: {}", i + 1, check(line, &grammar));
+        }
+    }
+
+    if fix {
+        for (i, line) in lines.iter().enumerate() {
+            let (repaired, edits) = repair(line, &grammar);
+            if !edits.is_empty() {
+                let edits: Vec<String> = edits.iter().map(|e| e.to_string()).collect();
+                println!("{}: {} ({})", i + 1, repaired, edits.join(", "));
+            }
+        }
+    }
+
     Ok(())
 }
 
-fn corruption_score(lines: &[&str]) -> u32 {
-    lines.iter().filter_map(|line| verify(*line).err()).sum()
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct BracketPair {
+    open: char,
+    close: char,
+    corruption_score: u32,
+    completion_score: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Grammar {
+    pairs: Vec<BracketPair>,
+}
+
+impl Grammar {
+    fn standard() -> Grammar {
+        Grammar {
+            pairs: vec![
+                BracketPair {
+                    open: '(',
+                    close: ')',
+                    corruption_score: 3,
+                    completion_score: 1,
+                },
+                BracketPair {
+                    open: '[',
+                    close: ']',
+                    corruption_score: 57,
+                    completion_score: 2,
+                },
+                BracketPair {
+                    open: '{',
+                    close: '}',
+                    corruption_score: 1197,
+                    completion_score: 3,
+                },
+                BracketPair {
+                    open: '<',
+                    close: '>',
+                    corruption_score: 25137,
+                    completion_score: 4,
+                },
+            ],
+        }
+    }
+
+    fn opening(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|pair| pair.open == c)
+    }
+
+    fn closing(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|pair| pair.close == c)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Diagnostic {
+    Complete,
+    // the closing character at `position` (in chars) does not match. `expected` is `None` if
+    // there was no open chunk left.
+    Corrupted {
+        position: usize,
+        found: char,
+        expected: Option<char>,
+    },
+    Incomplete {
+        completion: String,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Complete => write!(f, "complete"),
+            Diagnostic::Corrupted {
+                position,
+                found,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "corrupted at position {}: expected '{}', but found '{}'",
+                position, expected, found
+            ),
+            Diagnostic::Corrupted {
+                position,
+                found,
+                expected: None,
+            } => write!(
+                f,
+                "corrupted at position {}: found '{}', but there is no open chunk",
+                position, found
+            ),
+            Diagnostic::Incomplete { completion } => {
+                write!(f, "incomplete, complete with '{}'", completion)
+            }
+        }
+    }
+}
+
+fn corruption_score(lines: &[&str], grammar: &Grammar) -> u32 {
+    lines
+        .iter()
+        .filter_map(|line| match check(line, grammar) {
+            Diagnostic::Corrupted { found, .. } => grammar
+                .closing(found)
+                .map(|pair| grammar.pairs[pair].corruption_score),
+            _ => None,
+        })
+        .sum()
 }
 
-fn completion_score(lines: &[&str]) -> u64 {
-    let mut scores: Vec<u64> = lines.iter().filter_map(|line| verify(*line).ok()).collect();
+fn completion_score(lines: &[&str], grammar: &Grammar) -> u64 {
+    let mut scores: Vec<u64> = lines
+        .iter()
+        .filter_map(|line| match check(line, grammar) {
+            Diagnostic::Corrupted { .. } => None,
+            Diagnostic::Complete => Some(0),
+            Diagnostic::Incomplete { completion } => {
+                Some(completion.chars().fold(0, |score, c| {
+                    score * 5
+                        + grammar
+                            .closing(c)
+                            .map(|pair| grammar.pairs[pair].completion_score)
+                            .unwrap_or(0)
+                }))
+            }
+        })
+        .collect();
     scores.sort_unstable();
     scores.get(scores.len() / 2).copied().unwrap_or(0)
 }
 
-fn verify(line: &str) -> Result<u64, u32> {
-    let mut stack: Vec<char> = Vec::with_capacity(line.len());
-    for c in line.chars() {
-        match c {
-            '[' | '(' | '{' | '<' => stack.push(c),
-            ')' => match stack.pop() {
-                Some('(') => (),
-                _ => {
-                    return Err(3);
-                }
-            },
-            ']' => match stack.pop() {
-                Some('[') => (),
-                _ => {
-                    return Err(57);
-                }
-            },
-            '}' => match stack.pop() {
-                Some('{') => (),
-                _ => {
-                    return Err(1197);
-                }
-            },
-            '>' => match stack.pop() {
-                Some('<') => (),
-                _ => {
-                    return Err(25137);
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Step {
+    // the character is not part of the grammar, or it opened or closed a chunk as it should
+    Fine,
+    // the closing character does not match the open chunk that was popped from the stack (if any)
+    Mismatch(Option<usize>),
+}
+
+// Advances the stack of open chunks (indices of bracket pairs) by one character.
+fn step(c: char, grammar: &Grammar, stack: &mut Vec<usize>) -> Step {
+    if let Some(pair) = grammar.opening(c) {
+        stack.push(pair);
+    } else if let Some(pair) = grammar.closing(c) {
+        match stack.pop() {
+            Some(open) if open == pair => (),
+            open => return Step::Mismatch(open),
+        }
+    }
+    Step::Fine
+}
+
+fn completion(stack: &[usize], grammar: &Grammar) -> String {
+    stack
+        .iter()
+        .rev()
+        .map(|pair| grammar.pairs[*pair].close)
+        .collect()
+}
+
+// Characters that are not part of the grammar are ignored.
+fn check(line: &str, grammar: &Grammar) -> Diagnostic {
+    let mut stack: Vec<usize> = Vec::with_capacity(line.len());
+    for (position, c) in line.chars().enumerate() {
+        if let Step::Mismatch(open) = step(c, grammar, &mut stack) {
+            return Diagnostic::Corrupted {
+                position,
+                found: c,
+                expected: open.map(|open| grammar.pairs[open].close),
+            };
+        }
+    }
+    if stack.is_empty() {
+        Diagnostic::Complete
+    } else {
+        Diagnostic::Incomplete {
+            completion: completion(&stack, grammar),
+        }
+    }
+}
+
+// positions are char positions in the original line
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Edit {
+    Replace {
+        position: usize,
+        from: char,
+        to: char,
+    },
+    Delete {
+        position: usize,
+        c: char,
+    },
+    Append {
+        completion: String,
+    },
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::Replace { position, from, to } => {
+                write!(f, "replace '{}' at {} with '{}'", from, position, to)
+            }
+            Edit::Delete { position, c } => write!(f, "delete '{}' at {}", c, position),
+            Edit::Append { completion } => write!(f, "append '{}'", completion),
+        }
+    }
+}
+
+// Finds the fewest edits that make the line complete. Every closing character can be kept (if it
+// closes the open chunk), replaced by the expected one, or deleted, and completing whatever is
+// still open at the end counts as one more edit. This is a 0-1 BFS over the position and the stack
+// of open chunks, so the first state that reaches the end of the line needs the fewest edits.
+// Greedily replacing every wrong closer is not enough: "(])" only needs the ']' deleted.
+fn repair(line: &str, grammar: &Grammar) -> (String, Vec<Edit>) {
+    let chars: Vec<char> = line.chars().collect();
+    let done = chars.len() + 1;
+    let mut states: Vec<RepairState> = vec![RepairState {
+        position: 0,
+        stack: Vec::new(),
+        previous: None,
+        emitted: None,
+        edit: None,
+    }];
+    let mut queue: VecDeque<usize> = VecDeque::from([0]);
+    let mut visited: HashSet<(usize, Vec<usize>)> = HashSet::new();
+
+    while let Some(index) = queue.pop_front() {
+        let state = &states[index];
+        if state.position == done {
+            return states_to_repair(&states, index);
+        }
+        if !visited.insert((state.position, state.stack.clone())) {
+            continue;
+        }
+        let (position, stack) = (state.position, state.stack.clone());
+        // (next stack, emitted character, edit), an edit costs one
+        let mut next: Vec<(Vec<usize>, Option<char>, Option<Edit>)> = Vec::with_capacity(3);
+        if position == chars.len() {
+            let edit = (!stack.is_empty()).then(|| Edit::Append {
+                completion: completion(&stack, grammar),
+            });
+            next.push((Vec::new(), None, edit));
+        } else {
+            let c = chars[position];
+            let mut advanced = stack.clone();
+            match step(c, grammar, &mut advanced) {
+                Step::Fine => next.push((advanced, Some(c), None)),
+                Step::Mismatch(open) => {
+                    if let Some(open) = open {
+                        let to = grammar.pairs[open].close;
+                        next.push((
+                            advanced,
+                            Some(to),
+                            Some(Edit::Replace {
+                                position,
+                                from: c,
+                                to,
+                            }),
+                        ));
+                    }
                 }
-            },
-            _ => (),
-        }
-    }
-    Ok(stack.iter().rev().fold(0, |score, c| {
-        score * 5
-            + match c {
-                '(' => 1,
-                '[' => 2,
-                '{' => 3,
-                '<' => 4,
-                _ => 0,
             }
-    }))
+            if grammar.closing(c).is_some() {
+                next.push((stack, None, Some(Edit::Delete { position, c })));
+            }
+        }
+        for (stack, emitted, edit) in next {
+            let costs_nothing = edit.is_none();
+            states.push(RepairState {
+                position: position + 1,
+                stack,
+                previous: Some(index),
+                emitted,
+                edit,
+            });
+            if costs_nothing {
+                queue.push_front(states.len() - 1);
+            } else {
+                queue.push_back(states.len() - 1);
+            }
+        }
+    }
+    unreachable!("deleting all closing characters and completing the rest always works")
+}
+
+// one node of the search in `repair`, with the way back to the start
+struct RepairState {
+    position: usize,
+    stack: Vec<usize>,
+    previous: Option<usize>,
+    emitted: Option<char>,
+    edit: Option<Edit>,
+}
+
+fn states_to_repair(states: &[RepairState], last: usize) -> (String, Vec<Edit>) {
+    let mut path: Vec<&RepairState> = Vec::new();
+    let mut current = Some(last);
+    while let Some(index) = current {
+        path.push(&states[index]);
+        current = states[index].previous;
+    }
+    path.reverse();
+    let mut repaired: String = path.iter().filter_map(|state| state.emitted).collect();
+    let edits: Vec<Edit> = path.iter().filter_map(|state| state.edit.clone()).collect();
+    if let Some(Edit::Append { completion }) = edits.last() {
+        repaired.push_str(completion);
+    }
+    (repaired, edits)
+}
+
+// one bracket pair per line: opening and closing character, corruption score, completion score
+fn parse_grammar(input: &str) -> Result<Grammar, String> {
+    let pairs = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_bracket_pair)
+        .collect::<Result<Vec<BracketPair>, String>>()?;
+    for (i, pair) in pairs.iter().enumerate() {
+        if pairs[..i].iter().any(|other| {
+            [other.open, other.close]
+                .iter()
+                .any(|c| *c == pair.open || *c == pair.close)
+        }) {
+            return Err(format!(
+                "Characters of the pair '{}{}' are used more than once",
+                pair.open, pair.close
+            ));
+        }
+        if pair.open == pair.close {
+            return Err(format!(
+                "Opening and closing character of '{}{}' are the same",
+                pair.open, pair.close
+            ));
+        }
+    }
+    Ok(Grammar { pairs })
+}
+
+fn parse_bracket_pair(line: &str) -> Result<BracketPair, String> {
+    let mut parts = line.split_whitespace();
+    let mut next_char = |name: &str| -> Result<char, String> {
+        let part = parts
+            .next()
+            .ok_or_else(|| format!("Missing {} in line '{}'", name, line))?;
+        let mut chars = part.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(format!(
+                "Expected a single character for {} in line '{}'",
+                name, line
+            )),
+        }
+    };
+    let open = next_char("opening character")?;
+    let close = next_char("closing character")?;
+    let corruption_score = parts
+        .next()
+        .ok_or_else(|| format!("Missing corruption score in line '{}'", line))?
+        .parse::<u32>()
+        .map_err(|e| format!("Unable to parse corruption score in line '{}': {}", line, e))?;
+    let completion_score = parts
+        .next()
+        .ok_or_else(|| format!("Missing completion score in line '{}'", line))?
+        .parse::<u64>()
+        .map_err(|e| format!("Unable to parse completion score in line '{}': {}", line, e))?;
+    Ok(BracketPair {
+        open,
+        close,
+        corruption_score,
+        completion_score,
+    })
 }
 
 #[cfg(test)]
@@ -94,7 +450,7 @@ mod test {
         let lines: Vec<&str> = EXAMPLE_INPUT.lines().collect();
 
         // when
-        let score = corruption_score(&lines);
+        let score = corruption_score(&lines, &Grammar::standard());
 
         // then
         assert_eq!(score, 26397);
@@ -106,9 +462,184 @@ mod test {
         let lines: Vec<&str> = EXAMPLE_INPUT.lines().collect();
 
         // when
-        let score = completion_score(&lines);
+        let score = completion_score(&lines, &Grammar::standard());
 
         // then
         assert_eq!(score, 288957);
     }
+
+    #[test]
+    fn check_works_for_corrupted_line() {
+        // when
+        let diagnostic = check("{([(<{}[<>[]}>{[]{[(<()>", &Grammar::standard());
+
+        // then
+        assert_eq!(
+            diagnostic,
+            Diagnostic::Corrupted {
+                position: 12,
+                found: '}',
+                expected: Some(']')
+            }
+        );
+    }
+
+    #[test]
+    fn check_works_for_incomplete_line() {
+        // when
+        let diagnostic = check("[({(<(())[]>[[{[]{<()<>>", &Grammar::standard());
+
+        // then
+        assert_eq!(
+            diagnostic,
+            Diagnostic::Incomplete {
+                completion: "}}]])})]".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn check_works_for_closer_without_open_chunk() {
+        // when
+        let diagnostic = check("()]", &Grammar::standard());
+
+        // then
+        assert_eq!(
+            diagnostic,
+            Diagnostic::Corrupted {
+                position: 2,
+                found: ']',
+                expected: None
+            }
+        );
+    }
+
+    #[test]
+    fn check_works_with_custom_grammar() {
+        // given
+        let grammar = parse_grammar("/ \\ 10 1\n« » 20 2\n").expect("Expected successful parsing");
+
+        // when
+        let complete = check("/«»\\", &grammar);
+        let corrupted = check("/«\\", &grammar);
+        let incomplete = check("«/", &grammar);
+        let lines = ["/«\\", "«/"];
+
+        // then
+        assert_eq!(complete, Diagnostic::Complete);
+        assert_eq!(
+            corrupted,
+            Diagnostic::Corrupted {
+                position: 2,
+                found: '\\',
+                expected: Some('»')
+            }
+        );
+        assert_eq!(
+            incomplete,
+            Diagnostic::Incomplete {
+                completion: "\\»".to_owned()
+            }
+        );
+        assert_eq!(corruption_score(&lines, &grammar), 10);
+        assert_eq!(completion_score(&lines, &grammar), 7);
+    }
+
+    #[test]
+    fn parse_grammar_rejects_reused_characters() {
+        assert!(parse_grammar("( ) 3 1\n[ ( 57 2\n").is_err());
+    }
+
+    #[test]
+    fn repair_replaces_wrong_closer_and_completes() {
+        // when
+        let (repaired, edits) = repair("{([(<{}[<>[]}>{[]{[(<()>", &Grammar::standard());
+
+        // then
+        assert_eq!(repaired, "{([(<{}[<>[]]>{[]{[(<()>)]}})])}");
+        assert_eq!(
+            edits,
+            vec![
+                Edit::Replace {
+                    position: 12,
+                    from: '}',
+                    to: ']'
+                },
+                Edit::Append {
+                    completion: ")]}})])}".to_owned()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn repair_deletes_closer_without_open_chunk() {
+        // when
+        let (repaired, edits) = repair("()](", &Grammar::standard());
+
+        // then
+        assert_eq!(repaired, "()()");
+        assert_eq!(
+            edits,
+            vec![
+                Edit::Delete {
+                    position: 2,
+                    c: ']'
+                },
+                Edit::Append {
+                    completion: ")".to_owned()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn repair_prefers_deleting_when_it_needs_fewer_edits() {
+        // when
+        let (repaired, edits) = repair("(])", &Grammar::standard());
+
+        // then
+        // replacing ']' by ')' would need another edit for the last ')'
+        assert_eq!(repaired, "()");
+        assert_eq!(
+            edits,
+            vec![Edit::Delete {
+                position: 1,
+                c: ']'
+            }]
+        );
+    }
+
+    #[test]
+    fn repair_replaces_when_it_needs_fewer_edits() {
+        // when
+        let (repaired, edits) = repair("(]", &Grammar::standard());
+
+        // then
+        // deleting ']' would need another edit to complete the line
+        assert_eq!(repaired, "()");
+        assert_eq!(
+            edits,
+            vec![Edit::Replace {
+                position: 1,
+                from: ']',
+                to: ')'
+            }]
+        );
+    }
+
+    #[test]
+    fn repair_makes_all_example_lines_complete() {
+        for line in EXAMPLE_INPUT.lines() {
+            // when
+            let (repaired, edits) = repair(line, &Grammar::standard());
+
+            // then
+            assert_eq!(check(&repaired, &Grammar::standard()), Diagnostic::Complete);
+            assert_eq!(
+                edits.is_empty(),
+                check(line, &Grammar::standard()) == Diagnostic::Complete
+            );
+        }
+    }
 }
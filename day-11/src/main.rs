@@ -1,14 +1,43 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use std::fs::read_to_string;
+use std::fmt;
+use std::fs::{read_to_string, write};
 use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
-    let initial_map = parse(&content)?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
+    let mut initial_map = parse(&content)?;
+
+    // records until all octopuses are in sync, or for a fixed number of steps
+    let mut n_record_steps: Option<u64> = None;
+    let mut animate = false;
+    let mut pgm_directory: Option<String> = None;
+    for arg in env::args().skip(2) {
+        if let Some(threshold) = arg.strip_prefix("--threshold=") {
+            initial_map = initial_map.with_flash_threshold(
+                threshold
+                    .parse()
+                    .map_err(|e| format!("Unable to parse flash threshold: {}", e))?,
+            )?;
+        } else if arg == "--animate" {
+            animate = true;
+        } else if let Some(directory) = arg.strip_prefix("--pgm=") {
+            pgm_directory = Some(directory.to_owned());
+        } else if let Some(n_steps) = arg.strip_prefix("--steps=") {
+            n_record_steps = Some(
+                n_steps
+                    .parse()
+                    .map_err(|e| format!("Unable to parse number of steps: {}", e))?,
+            );
+        } else {
+            return Err(format!("Unknown option: {}", arg));
+        }
+    }
 
     let flashes_after_100 = run_steps(initial_map.clone(), 100);
     println!(
@@ -16,11 +45,34 @@ fn main() -> Result<(), String> {
         flashes_after_100
     );
 
-    let steps_until_sync = run_until_sync(initial_map);
-    println!(
-        "After {} steps, all octopuses flash in sync",
-        steps_until_sync
-    );
+    match run_until_sync(initial_map.clone()) {
+        Ok(steps_until_sync) => println!(
+            "After {} steps, all octopuses flash in sync",
+            steps_until_sync
+        ),
+        Err(no_sync) => println!("The octopuses {}", no_sync),
+    }
+
+    if !animate && pgm_directory.is_none() {
+        return Ok(());
+    }
+
+    let recording = match n_record_steps {
+        Some(n_steps) => record_steps(initial_map, n_steps),
+        None => record_until_sync(initial_map),
+    };
+    if animate {
+        for frame in &recording.frames {
+            print!("\x1b[2J\x1b[H{}", render_frame_ansi(&recording, frame));
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    if let Some(directory) = pgm_directory {
+        for (i, frame) in recording.frames.iter().enumerate() {
+            let path = Path::new(&directory).join(format!("frame_{:04}.pgm", i));
+            write(&path, render_frame_pgm(&recording, frame)).map_err(|e| e.to_string())?;
+        }
+    }
 
     Ok(())
 }
@@ -35,23 +87,156 @@ fn run_steps(mut octo_map: OctoMap, n_steps: u64) -> u64 {
     flash_counter
 }
 
-fn run_until_sync(mut octo_map: OctoMap) -> u64 {
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct NoSync {
+    cycle_start: u64,
+    cycle_length: u64,
+}
+
+impl fmt::Display for NoSync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "never synchronise; cycle of length {} starting at step {}",
+            self.cycle_length, self.cycle_start
+        )
+    }
+}
+
+fn run_until_sync(octo_map: OctoMap) -> Result<u64, NoSync> {
+    run_until_sync_with(octo_map, |_| ())
+}
+
+// The simulation is deterministic and has only finitely many states, so if a state repeats before
+// all octopuses flash at once, they never will. `on_step` is called with the state after each step.
+fn run_until_sync_with(
+    mut octo_map: OctoMap,
+    mut on_step: impl FnMut(&OctoMap),
+) -> Result<u64, NoSync> {
+    let mut seen: HashMap<Vec<u8>, u64> = HashMap::new();
     let mut counter = 0;
     let mut flashes = 0;
     while flashes < octo_map.energy.len() as u64 {
+        if let Some(cycle_start) = seen.insert(octo_map.energy.clone(), counter) {
+            return Err(NoSync {
+                cycle_start,
+                cycle_length: counter - cycle_start,
+            });
+        }
         let (next_octo_map, f) = run_step(octo_map);
         flashes = f;
         octo_map = next_octo_map;
         counter += 1;
+        on_step(&octo_map);
+    }
+    Ok(counter)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Frame {
+    energy: Vec<u8>,
+    flashed: Vec<bool>,
+}
+
+// the first frame is the initial state, followed by one frame per step
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Recording {
+    width: usize,
+    // to scale the brightness of octopuses that did not flash
+    flash_threshold: u8,
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    fn new(octo_map: &OctoMap) -> Recording {
+        Recording {
+            width: octo_map.width,
+            flash_threshold: octo_map.flash_threshold,
+            frames: vec![Frame {
+                energy: octo_map.energy.clone(),
+                flashed: vec![false; octo_map.energy.len()],
+            }],
+        }
     }
-    counter
+
+    fn record(&mut self, octo_map: &OctoMap) {
+        // flashing octopuses are exactly the ones that are reset to 0
+        self.frames.push(Frame {
+            energy: octo_map.energy.clone(),
+            flashed: octo_map.energy.iter().map(|e| *e == 0).collect(),
+        });
+    }
+}
+
+fn record_steps(mut octo_map: OctoMap, n_steps: u64) -> Recording {
+    let mut recording = Recording::new(&octo_map);
+    for _ in 0..n_steps {
+        octo_map = run_step(octo_map).0;
+        recording.record(&octo_map);
+    }
+    recording
+}
+
+// if the octopuses never synchronise, this records until the first repeated state
+fn record_until_sync(octo_map: OctoMap) -> Recording {
+    let mut recording = Recording::new(&octo_map);
+    // the result is already visible in the last frame
+    let _ = run_until_sync_with(octo_map, |o| recording.record(o));
+    recording
+}
+
+// flashing octopuses are bright and bold, the others get darker the less energy they have
+fn render_frame_ansi(recording: &Recording, frame: &Frame) -> String {
+    let mut result = String::with_capacity(frame.energy.len() * 16);
+    for (i, (energy, flashed)) in frame.energy.iter().zip(&frame.flashed).enumerate() {
+        if *flashed {
+            result.push_str("\x1b[1;97m0");
+        } else {
+            // grey levels go from 232 to 255, leave the brightest ones for flashing octopuses
+            let grey = 232 + *energy as u32 * 18 / recording.flash_threshold.max(1) as u32;
+            result.push_str(&format!("\x1b[0;38;5;{}m{}", grey, energy));
+        }
+        if (i + 1).is_multiple_of(recording.width) {
+            result.push_str("\x1b[0m\n");
+        }
+    }
+    result
+}
+
+// plain text PGM, flashing octopuses are white, the others at most light grey
+fn render_frame_pgm(recording: &Recording, frame: &Frame) -> String {
+    let scale = recording.flash_threshold.max(1) as u32;
+    let height = frame.energy.len() / recording.width;
+    let mut result = format!("P2\n{} {}\n255\n", recording.width, height);
+    for row in frame
+        .energy
+        .chunks(recording.width)
+        .zip(frame.flashed.chunks(recording.width))
+    {
+        let pixels: Vec<String> = row
+            .0
+            .iter()
+            .zip(row.1)
+            .map(|(energy, flashed)| {
+                if *flashed {
+                    255
+                } else {
+                    *energy as u32 * 180 / scale
+                }
+                .to_string()
+            })
+            .collect();
+        result.push_str(&pixels.join(" "));
+        result.push('\n');
+    }
+    result
 }
 
 fn run_step(mut octo_map: OctoMap) -> (OctoMap, u64) {
     let mut queue: VecDeque<usize> = VecDeque::with_capacity(octo_map.energy.len());
     for (i, e) in octo_map.energy.iter_mut().enumerate() {
         *e += 1;
-        if *e == 10 {
+        if *e == octo_map.flash_threshold + 1 {
             queue.push_back(i);
         }
     }
@@ -59,15 +244,20 @@ fn run_step(mut octo_map: OctoMap) -> (OctoMap, u64) {
     while let Some(pos) = queue.pop_front() {
         flash_count += 1;
         for n in octo_map.moore_neighbours(pos) {
-            octo_map.energy[n] += 1;
-            if octo_map.energy[n] == 10 {
-                queue.push_back(n);
+            // octopuses that already flashed are reset anyway, so there is no need to go beyond
+            // threshold + 1, which would overflow for thresholds close to u8::MAX
+            if octo_map.energy[n] <= octo_map.flash_threshold {
+                octo_map.energy[n] += 1;
+                if octo_map.energy[n] == octo_map.flash_threshold + 1 {
+                    queue.push_back(n);
+                }
             }
         }
     }
 
+    let flash_threshold = octo_map.flash_threshold;
     for e in octo_map.energy.iter_mut() {
-        if *e > 9 {
+        if *e > flash_threshold {
             *e = 0;
         }
     }
@@ -77,21 +267,42 @@ fn run_step(mut octo_map: OctoMap) -> (OctoMap, u64) {
 
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
 struct OctoMap {
-    edge_length: usize,
+    width: usize,
+    height: usize,
+    // octopuses with more energy than this flash
+    flash_threshold: u8,
     energy: Vec<u8>,
 }
 
 // I know I don't really need an iterator for that but I want one!
 struct MooreNeighbours {
-    edge_length: isize,
+    width: isize,
+    height: isize,
     center: isize,
     neighbour_index: usize,
 }
 
 impl OctoMap {
+    fn with_flash_threshold(self, flash_threshold: u8) -> Result<OctoMap, String> {
+        if flash_threshold == u8::MAX {
+            return Err(format!("Flash threshold must be less than {}", u8::MAX));
+        }
+        if let Some(e) = self.energy.iter().find(|e| **e > flash_threshold) {
+            return Err(format!(
+                "Octopus with energy {} is above the flash threshold {}",
+                e, flash_threshold
+            ));
+        }
+        Ok(OctoMap {
+            flash_threshold,
+            ..self
+        })
+    }
+
     fn moore_neighbours(&self, pos: usize) -> MooreNeighbours {
         MooreNeighbours {
-            edge_length: self.edge_length as isize,
+            width: self.width as isize,
+            height: self.height as isize,
             center: pos as isize,
             neighbour_index: 0,
         }
@@ -116,12 +327,12 @@ impl Iterator for MooreNeighbours {
         while self.neighbour_index < MOORE_NEIGHBOURS.len() {
             let (dx, dy) = MOORE_NEIGHBOURS[self.neighbour_index];
             self.neighbour_index += 1;
-            let x = self.center % self.edge_length + dx;
-            let y = self.center / self.edge_length + dy;
-            if x < 0 || y < 0 || x >= self.edge_length || y >= self.edge_length {
+            let x = self.center % self.width + dx;
+            let y = self.center / self.width + dy;
+            if x < 0 || y < 0 || x >= self.width || y >= self.height {
                 continue;
             } else {
-                return Some((x + y * self.edge_length) as usize);
+                return Some((x + y * self.width) as usize);
             }
         }
         None
@@ -131,19 +342,36 @@ impl Iterator for MooreNeighbours {
 }
 
 fn parse(content: &str) -> Result<OctoMap, String> {
-    let energy: Vec<u8> = content
-        .chars()
-        .filter_map(|c| c.to_digit(10).map(|d| d as u8))
-        .collect();
-    if energy.len() != 100 {
+    let rows: Vec<Vec<u8>> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.trim()
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|d| d as u8)
+                        .ok_or_else(|| format!("Unexpected character '{}' in line '{}'", c, line))
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<u8>>, String>>()?;
+    let width = rows
+        .first()
+        .map(|row| row.len())
+        .ok_or_else(|| "Expected at least one dumbo octopus".to_owned())?;
+    if let Some(row) = rows.iter().find(|row| row.len() != width) {
         return Err(format!(
-            "Expected exactly 100 dumbo octopuses, found {}",
-            energy.len()
+            "Expected {} dumbo octopuses in every row, found {}",
+            width,
+            row.len()
         ));
     }
     Ok(OctoMap {
-        edge_length: 10,
-        energy,
+        width,
+        height: rows.len(),
+        flash_threshold: 9,
+        energy: rows.concat(),
     })
 }
 
@@ -183,7 +411,164 @@ mod test {
         let steps = run_until_sync(before);
 
         // then
-        assert_eq!(steps, 195);
+        assert_eq!(steps, Ok(195));
+    }
+
+    #[test]
+    fn record_steps_matches_run_steps() {
+        // given
+        let before = parse(EXAMPLE_INPUT).expect("expected successful parsing");
+
+        // when
+        let recording = record_steps(before.clone(), 10);
+
+        // then
+        assert_eq!(recording.frames.len(), 11);
+        assert_eq!(recording.frames[0].energy, before.energy);
+        let recorded_flashes = recording
+            .frames
+            .iter()
+            .flat_map(|frame| &frame.flashed)
+            .filter(|f| **f)
+            .count();
+        assert_eq!(recorded_flashes as u64, run_steps(before, 10));
+    }
+
+    #[test]
+    fn record_until_sync_ends_with_all_flashing() {
+        // given
+        let before = parse(EXAMPLE_INPUT).expect("expected successful parsing");
+
+        // when
+        let recording = record_until_sync(before);
+
+        // then
+        assert_eq!(recording.frames.len(), 196);
+        assert!(recording.frames[195].flashed.iter().all(|f| *f));
+        assert!(!recording.frames[194].flashed.iter().all(|f| *f));
+    }
+
+    #[test]
+    fn render_frame_pgm_works_for_example() {
+        // given
+        let recording = Recording {
+            width: 2,
+            flash_threshold: 9,
+            frames: vec![Frame {
+                energy: vec![0, 1, 9, 0],
+                flashed: vec![true, false, false, false],
+            }],
+        };
+
+        // when
+        let pgm = render_frame_pgm(&recording, &recording.frames[0]);
+
+        // then
+        assert_eq!(pgm, "P2\n2 2\n255\n255 20\n180 0\n");
+    }
+
+    #[test]
+    fn render_frame_scales_by_flash_threshold() {
+        // given
+        let recording = Recording {
+            width: 2,
+            flash_threshold: 200,
+            frames: vec![Frame {
+                energy: vec![0, 100, 200, 0],
+                flashed: vec![true, false, false, false],
+            }],
+        };
+
+        // when
+        let pgm = render_frame_pgm(&recording, &recording.frames[0]);
+        let ansi = render_frame_ansi(&recording, &recording.frames[0]);
+
+        // then
+        assert_eq!(pgm, "P2\n2 2\n255\n255 90\n180 0\n");
+        assert_eq!(
+            ansi,
+            "\x1b[1;97m0\x1b[0;38;5;241m100\x1b[0m\n\x1b[0;38;5;250m200\x1b[0;38;5;232m0\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn run_until_sync_works_for_non_square_grid() {
+        // given
+        let before = parse("9999\n9999\n").expect("expected successful parsing");
+
+        // when
+        let steps = run_until_sync(before);
+
+        // then
+        assert_eq!(steps, Ok(1));
+    }
+
+    #[test]
+    fn run_until_sync_reports_never_synchronising_octopuses() {
+        // given
+        // with a high threshold, the two octopuses never catch up with each other
+        let before = parse("05\n")
+            .expect("expected successful parsing")
+            .with_flash_threshold(20)
+            .expect("expected valid threshold");
+
+        // when
+        let steps = run_until_sync(before);
+
+        // then
+        assert_eq!(
+            steps,
+            Err(NoSync {
+                cycle_start: 0,
+                cycle_length: 20
+            })
+        );
+        assert_eq!(
+            steps.unwrap_err().to_string(),
+            "never synchronise; cycle of length 20 starting at step 0"
+        );
+    }
+
+    #[test]
+    fn run_step_respects_flash_threshold() {
+        // given
+        let before = parse("45\n")
+            .expect("expected successful parsing")
+            .with_flash_threshold(5)
+            .expect("expected valid threshold");
+
+        // when
+        let (after, flashes) = run_step(before);
+
+        // then
+        assert_eq!(after.energy, vec![0, 0]);
+        assert_eq!(flashes, 2);
+    }
+
+    #[test]
+    fn run_step_works_for_thresholds_close_to_u8_max() {
+        // given
+        let before = parse("999\n999\n999\n")
+            .expect("expected successful parsing")
+            .with_flash_threshold(250)
+            .expect("expected valid threshold");
+        let mut almost_flashing = before.clone();
+        almost_flashing.energy = vec![250; 9];
+
+        // when
+        let (after, flashes) = run_step(before);
+        let (after_flashing, all_flashes) = run_step(almost_flashing);
+
+        // then
+        assert_eq!(after.energy, vec![10; 9]);
+        assert_eq!(flashes, 0);
+        assert_eq!(after_flashing.energy, vec![0; 9]);
+        assert_eq!(all_flashes, 9);
+    }
+
+    #[test]
+    fn parse_rejects_ragged_grids() {
+        assert!(parse("123\n12\n").is_err());
     }
 
     #[test]
@@ -1,24 +1,96 @@
 use std::collections::HashMap;
 use std::env;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write};
 use std::path::Path;
 
 fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
+
+    let mut start = START_VERTICE.to_owned();
+    let mut end = END_VERTICE.to_owned();
+    let mut policy: Option<RevisitPolicy> = None;
+    let mut list = false;
+    let mut dot_filename: Option<String> = None;
+    let mut dot_path: Option<usize> = None;
+    let mut dot_usage = false;
+    for arg in env::args().skip(2) {
+        if let Some(cave) = arg.strip_prefix("--start=") {
+            start = cave.to_owned();
+        } else if let Some(cave) = arg.strip_prefix("--end=") {
+            end = cave.to_owned();
+        } else if let Some(p) = arg.strip_prefix("--policy=") {
+            policy = Some(parse_policy(p)?);
+        } else if arg == "--list" {
+            list = true;
+        } else if let Some(f) = arg.strip_prefix("--dot=") {
+            dot_filename = Some(f.to_owned());
+        } else if let Some(index) = arg.strip_prefix("--dot-path=") {
+            dot_path = Some(
+                index
+                    .parse()
+                    .map_err(|e| format!("Unable to parse path index: {}", e))?,
+            );
+        } else if arg == "--dot-usage" {
+            dot_usage = true;
+        } else {
+            return Err(format!("Unknown option: {}", arg));
+        }
+    }
 
     let edges = parse_edges(&content)?;
 
-    let all_paths = find_all_paths(&edges, vec![START_VERTICE], true);
+    let graph = CaveGraph::new(&edges, &start, &end)?;
+
+    let n_paths = count_paths(&graph, true);
     println!(
-        "There are {} paths from start to end that visit small caves at most once",
-        all_paths.len()
+        "There are {} paths from {} to {} that visit small caves at most once",
+        n_paths, start, end
     );
 
-    let all_paths_puzzle_2 = find_all_paths(&edges, vec![START_VERTICE], false);
-    println!("There are {} paths from start to end that visit one small cave at most twice and other small caves at most once", all_paths_puzzle_2.len());
+    let n_paths_puzzle_2 = count_paths(&graph, false);
+    println!("There are {} paths from {} to {} that visit one small cave at most twice and other small caves at most once", n_paths_puzzle_2, start, end);
+
+    if let Some(policy) = policy {
+        let n_paths_policy = count_paths_with_policy(&graph, &policy);
+        println!(
+            "There are {} paths from {} to {} with the policy {:?}",
+            n_paths_policy, start, end, policy
+        );
+    }
+
+    if list {
+        for path in find_all_paths(&edges, vec![&start], &end, true) {
+            println!("{}", path.join(","));
+        }
+    }
+
+    if let Some(dot_filename) = dot_filename {
+        // highlighted paths are the ones from `--list`
+        let highlight = if let Some(index) = dot_path {
+            let paths = find_all_paths(&edges, vec![&start], &end, true);
+            let path = paths
+                .get(index)
+                .ok_or_else(|| format!("There is no path {}, only {} paths", index, paths.len()))?;
+            Highlight::Path(path.clone())
+        } else if dot_usage {
+            Highlight::Usage(edge_usage(&find_all_paths(
+                &edges,
+                vec![&start],
+                &end,
+                true,
+            )))
+        } else {
+            Highlight::Nothing
+        };
+        write(
+            Path::new(&dot_filename),
+            edges_to_dot(&edges, &start, &end, &highlight),
+        )
+        .map_err(|e| e.to_string())?;
+    }
 
     Ok(())
 }
@@ -26,13 +98,16 @@ fn main() -> Result<(), String> {
 const START_VERTICE: &str = "start";
 const END_VERTICE: &str = "end";
 
+// `path` must at least contain the start cave
 fn find_all_paths<'a>(
     edges: &'a Edges,
     path: Vec<&'a str>,
+    end: &str,
     visited_small_cave_twice: bool,
 ) -> Vec<Vec<&'a str>> {
-    let from: &str = path.last().unwrap_or(&START_VERTICE);
-    if from == END_VERTICE {
+    let start: &str = path.first().copied().unwrap_or(START_VERTICE);
+    let from: &str = path.last().copied().unwrap_or(START_VERTICE);
+    if from == end {
         return vec![path];
     }
     if let Some(vertices) = edges.get(&from) {
@@ -47,8 +122,8 @@ fn find_all_paths<'a>(
                         Some((vertice, visited_small_cave_twice))
                     } else if !visited_small_cave_twice
                         && count == 1
-                        && vertice != &START_VERTICE
-                        && vertice != &END_VERTICE
+                        && *vertice != start
+                        && *vertice != end
                     {
                         Some((vertice, true))
                     } else {
@@ -59,7 +134,7 @@ fn find_all_paths<'a>(
             .flat_map(|(vertice, visited_twice)| {
                 let mut p = path.clone();
                 p.push(vertice);
-                find_all_paths(edges, p, visited_twice)
+                find_all_paths(edges, p, end, visited_twice)
             })
             .collect()
     } else {
@@ -67,6 +142,232 @@ fn find_all_paths<'a>(
     }
 }
 
+// The caves with integer ids instead of names, so that a set of small caves fits into a bitmask.
+struct CaveGraph<'a> {
+    names: Vec<&'a str>,
+    neighbours: Vec<Vec<usize>>,
+    // bit of each small cave in the visited mask, `None` for large caves
+    small_cave_bits: Vec<Option<u32>>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> CaveGraph<'a> {
+    fn new(edges: &Edges<'a>, start: &str, end: &str) -> Result<CaveGraph<'a>, String> {
+        let mut names: Vec<&str> = edges.keys().copied().collect();
+        names.sort_unstable();
+        let ids: HashMap<&str, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let id = |name: &str| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| format!("Cave '{}' is not connected to anything", name))
+        };
+
+        let mut small_cave_bits: Vec<Option<u32>> = Vec::with_capacity(names.len());
+        let mut n_small_caves: u32 = 0;
+        for name in &names {
+            if is_large_cave(name) {
+                small_cave_bits.push(None);
+            } else if n_small_caves >= u64::BITS {
+                return Err(format!("More than {} small caves", u64::BITS));
+            } else {
+                small_cave_bits.push(Some(n_small_caves));
+                n_small_caves += 1;
+            }
+        }
+
+        let mut neighbours: Vec<Vec<usize>> = Vec::with_capacity(names.len());
+        for name in &names {
+            let vertices = &edges[name];
+            if is_large_cave(name) {
+                if let Some(other) = vertices.iter().find(|v| is_large_cave(v)) {
+                    return Err(format!(
+                        "Large caves '{}' and '{}' are connected, so there are infinitely many paths",
+                        name, other
+                    ));
+                }
+            }
+            neighbours.push(
+                vertices
+                    .iter()
+                    .map(|v| id(v))
+                    .collect::<Result<Vec<usize>, String>>()?,
+            );
+        }
+
+        let start = id(start)?;
+        let end = id(end)?;
+        Ok(CaveGraph {
+            names,
+            neighbours,
+            small_cave_bits,
+            start,
+            end,
+        })
+    }
+}
+
+// Same rules as `find_all_paths`, but only counts the paths. The number of paths from a cave only
+// depends on the small caves visited so far and whether a small cave was visited twice already.
+fn count_paths(graph: &CaveGraph, visited_small_cave_twice: bool) -> u64 {
+    let start_mask = graph.small_cave_bits[graph.start]
+        .map(|bit| 1u64 << bit)
+        .unwrap_or(0);
+    let mut memo: HashMap<(usize, u64, bool), u64> = HashMap::new();
+    count_paths_from(
+        graph,
+        graph.start,
+        start_mask,
+        visited_small_cave_twice,
+        &mut memo,
+    )
+}
+
+fn count_paths_from(
+    graph: &CaveGraph,
+    from: usize,
+    visited: u64,
+    visited_small_cave_twice: bool,
+    memo: &mut HashMap<(usize, u64, bool), u64>,
+) -> u64 {
+    if from == graph.end {
+        return 1;
+    }
+    if let Some(count) = memo.get(&(from, visited, visited_small_cave_twice)) {
+        return *count;
+    }
+    let mut count: u64 = 0;
+    for vertice in graph.neighbours[from].iter().copied() {
+        count += match graph.small_cave_bits[vertice] {
+            None => count_paths_from(graph, vertice, visited, visited_small_cave_twice, memo),
+            Some(bit) if visited & (1 << bit) == 0 => count_paths_from(
+                graph,
+                vertice,
+                visited | (1 << bit),
+                visited_small_cave_twice,
+                memo,
+            ),
+            Some(_)
+                if !visited_small_cave_twice && vertice != graph.start && vertice != graph.end =>
+            {
+                count_paths_from(graph, vertice, visited, true, memo)
+            }
+            Some(_) => 0,
+        };
+    }
+    memo.insert((from, visited, visited_small_cave_twice), count);
+    count
+}
+
+// Start and end caves are always visited only once.
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum RevisitPolicy {
+    // one small cave may be revisited this many times, all others are visited only once
+    OneCave(u8),
+    // small caves may be revisited this many times in total
+    Total(u8),
+    // maximum number of visits for each named small cave, caves without a limit are visited once
+    PerCave(HashMap<String, u8>),
+}
+
+impl RevisitPolicy {
+    // `visits` are the number of visits of each small cave so far, indexed by its bit. They are u16,
+    // since limits of u8::MAX revisits allow for one more visit than u8 can count.
+    fn allows_visit(&self, visits: &[u16], bit: usize, name: &str) -> bool {
+        let next = visits[bit] + 1;
+        match self {
+            RevisitPolicy::OneCave(limit) => {
+                next == 1
+                    || (visits[bit] <= *limit as u16
+                        && visits
+                            .iter()
+                            .enumerate()
+                            .all(|(other, v)| other == bit || *v <= 1))
+            }
+            RevisitPolicy::Total(limit) => {
+                let revisits: u32 = visits.iter().map(|v| v.saturating_sub(1) as u32).sum();
+                next == 1 || revisits < *limit as u32
+            }
+            RevisitPolicy::PerCave(limits) => next <= limits.get(name).copied().unwrap_or(1) as u16,
+        }
+    }
+}
+
+// e.g. "one:2", "total:3" or "caves:ab=2,cd=3"
+fn parse_policy(input: &str) -> Result<RevisitPolicy, String> {
+    let (kind, value) = input
+        .split_once(':')
+        .ok_or_else(|| format!("Unable to parse policy '{}'", input))?;
+    let parse_limit = |limit: &str| {
+        limit
+            .parse::<u8>()
+            .map_err(|e| format!("Unable to parse limit in policy '{}': {}", input, e))
+    };
+    match kind {
+        "one" => Ok(RevisitPolicy::OneCave(parse_limit(value)?)),
+        "total" => Ok(RevisitPolicy::Total(parse_limit(value)?)),
+        "caves" => value
+            .split(',')
+            .map(|cave_limit| {
+                let (cave, limit) = cave_limit
+                    .split_once('=')
+                    .ok_or_else(|| format!("Unable to parse cave limit '{}'", cave_limit))?;
+                Ok((cave.to_owned(), parse_limit(limit)?))
+            })
+            .collect::<Result<HashMap<String, u8>, String>>()
+            .map(RevisitPolicy::PerCave),
+        _ => Err(format!("Unknown policy '{}'", kind)),
+    }
+}
+
+// Like `count_paths`, but the visited set is not enough anymore, we need the number of visits for
+// each small cave.
+fn count_paths_with_policy(graph: &CaveGraph, policy: &RevisitPolicy) -> u64 {
+    let n_small_caves = graph.small_cave_bits.iter().flatten().count();
+    let mut visits: Vec<u16> = vec![0; n_small_caves];
+    if let Some(bit) = graph.small_cave_bits[graph.start] {
+        visits[bit as usize] = 1;
+    }
+    let mut memo: HashMap<(usize, Vec<u16>), u64> = HashMap::new();
+    count_paths_with_policy_from(graph, policy, graph.start, &mut visits, &mut memo)
+}
+
+fn count_paths_with_policy_from(
+    graph: &CaveGraph,
+    policy: &RevisitPolicy,
+    from: usize,
+    visits: &mut Vec<u16>,
+    memo: &mut HashMap<(usize, Vec<u16>), u64>,
+) -> u64 {
+    if from == graph.end {
+        return 1;
+    }
+    if let Some(count) = memo.get(&(from, visits.clone())) {
+        return *count;
+    }
+    let mut count: u64 = 0;
+    for vertice in graph.neighbours[from].iter().copied() {
+        match graph.small_cave_bits[vertice] {
+            None => count += count_paths_with_policy_from(graph, policy, vertice, visits, memo),
+            Some(bit) => {
+                let bit = bit as usize;
+                let allowed = if vertice == graph.start || vertice == graph.end {
+                    visits[bit] == 0
+                } else {
+                    policy.allows_visit(visits, bit, graph.names[vertice])
+                };
+                if allowed {
+                    visits[bit] += 1;
+                    count += count_paths_with_policy_from(graph, policy, vertice, visits, memo);
+                    visits[bit] -= 1;
+                }
+            }
+        }
+    }
+    memo.insert((from, visits.clone()), count);
+    count
+}
+
 fn is_large_cave(vertice: &str) -> bool {
     vertice
         .chars()
@@ -75,6 +376,89 @@ fn is_large_cave(vertice: &str) -> bool {
         .unwrap_or(false)
 }
 
+enum Highlight<'a> {
+    Nothing,
+    Path(Vec<&'a str>),
+    // how often each edge is used, with the caves of each edge in lexical order
+    Usage(HashMap<(&'a str, &'a str), u64>),
+}
+
+fn ordered_edge<'a>(v1: &'a str, v2: &'a str) -> (&'a str, &'a str) {
+    if v1 <= v2 {
+        (v1, v2)
+    } else {
+        (v2, v1)
+    }
+}
+
+fn edge_usage<'a>(paths: &[Vec<&'a str>]) -> HashMap<(&'a str, &'a str), u64> {
+    let mut usage: HashMap<(&str, &str), u64> = HashMap::with_capacity(64);
+    for path in paths {
+        for edge in path.windows(2) {
+            *usage.entry(ordered_edge(edge[0], edge[1])).or_insert(0) += 1;
+        }
+    }
+    usage
+}
+
+// Large caves are boxes, small caves are ellipses. Used edges are highlighted in red.
+fn edges_to_dot(edges: &Edges, start: &str, end: &str, highlight: &Highlight) -> String {
+    let mut caves: Vec<&str> = edges.keys().copied().collect();
+    caves.sort_unstable();
+
+    let mut result = String::from("graph caves {\n");
+    for cave in &caves {
+        let shape = if is_large_cave(cave) {
+            "box, style=filled, fillcolor=lightgrey"
+        } else {
+            "ellipse"
+        };
+        let peripheries = if *cave == start || *cave == end {
+            ", peripheries=2"
+        } else {
+            ""
+        };
+        result.push_str(&format!(
+            "    \"{}\" [shape={}{}];\n",
+            cave, shape, peripheries
+        ));
+    }
+
+    let path_edges: HashMap<(&str, &str), u64> = match highlight {
+        Highlight::Path(path) => edge_usage(std::slice::from_ref(path)),
+        _ => HashMap::new(),
+    };
+    let max_usage = match highlight {
+        Highlight::Usage(usage) => usage.values().max().copied().unwrap_or(0),
+        _ => 0,
+    };
+    for v1 in &caves {
+        let mut neighbours: Vec<&str> = edges[v1].iter().copied().filter(|v2| v1 <= v2).collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for v2 in neighbours {
+            let attributes = match highlight {
+                Highlight::Nothing => String::new(),
+                Highlight::Path(_) => match path_edges.get(&(v1, v2)) {
+                    Some(count) => format!(" [color=red, penwidth=3, label=\"{}\"]", count),
+                    None => String::new(),
+                },
+                Highlight::Usage(usage) => match usage.get(&(v1, v2)) {
+                    Some(count) => format!(
+                        " [color=red, penwidth={:.1}, label=\"{}\"]",
+                        1.0 + 7.0 * *count as f64 / max_usage as f64,
+                        count
+                    ),
+                    None => String::new(),
+                },
+            };
+            result.push_str(&format!("    \"{}\" -- \"{}\"{};\n", v1, v2, attributes));
+        }
+    }
+    result.push_str("}\n");
+    result
+}
+
 type Edges<'a> = HashMap<&'a str, Vec<&'a str>>;
 
 fn parse_edges(content: &str) -> Result<HashMap<&str, Vec<&str>>, String> {
@@ -116,7 +500,7 @@ b-end
         let edges = parse_edges(SIMPLE_EXAMPLE).expect("Expected successful parsing");
 
         // when
-        let paths = find_all_paths(&edges, vec![START_VERTICE], true);
+        let paths = find_all_paths(&edges, vec![START_VERTICE], END_VERTICE, true);
 
         // then
         let path_set: HashSet<Vec<&str>> = paths.into_iter().collect();
@@ -144,9 +528,231 @@ b-end
         let edges = parse_edges(SIMPLE_EXAMPLE).expect("Expected successful parsing");
 
         // when
-        let paths = find_all_paths(&edges, vec![START_VERTICE], false);
+        let paths = find_all_paths(&edges, vec![START_VERTICE], END_VERTICE, false);
 
         // then
         assert_eq!(paths.len(), 36);
     }
+
+    const LARGER_EXAMPLE: &str = r"fs-end
+he-DX
+fs-he
+start-DX
+pj-DX
+end-zg
+zg-sl
+zg-pj
+pj-he
+RW-he
+fs-DX
+pj-RW
+zg-RW
+start-pj
+he-WI
+zg-he
+pj-fs
+start-RW
+";
+
+    #[test]
+    fn count_paths_matches_find_all_paths() {
+        for input in [SIMPLE_EXAMPLE, LARGER_EXAMPLE] {
+            // given
+            let edges = parse_edges(input).expect("Expected successful parsing");
+            let graph = CaveGraph::new(&edges, START_VERTICE, END_VERTICE)
+                .expect("Expected valid cave graph");
+
+            for visited_small_cave_twice in [true, false] {
+                // when
+                let count = count_paths(&graph, visited_small_cave_twice);
+
+                // then
+                let paths = find_all_paths(
+                    &edges,
+                    vec![START_VERTICE],
+                    END_VERTICE,
+                    visited_small_cave_twice,
+                );
+                assert_eq!(count, paths.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn count_paths_works_for_larger_example() {
+        // given
+        let edges = parse_edges(LARGER_EXAMPLE).expect("Expected successful parsing");
+        let graph =
+            CaveGraph::new(&edges, START_VERTICE, END_VERTICE).expect("Expected valid cave graph");
+
+        // when
+        let count_1 = count_paths(&graph, true);
+        let count_2 = count_paths(&graph, false);
+
+        // then
+        assert_eq!(count_1, 226);
+        assert_eq!(count_2, 3509);
+    }
+
+    #[test]
+    fn cave_graph_rejects_connected_large_caves() {
+        // given
+        let edges = parse_edges("start-A\nA-B\nB-end\n").expect("Expected successful parsing");
+
+        // when
+        let graph = CaveGraph::new(&edges, START_VERTICE, END_VERTICE);
+
+        // then
+        assert!(graph.is_err());
+    }
+
+    #[test]
+    fn count_paths_with_policy_matches_count_paths() {
+        for input in [SIMPLE_EXAMPLE, LARGER_EXAMPLE] {
+            // given
+            let edges = parse_edges(input).expect("Expected successful parsing");
+            let graph = CaveGraph::new(&edges, START_VERTICE, END_VERTICE)
+                .expect("Expected valid cave graph");
+
+            // when
+            let once = count_paths_with_policy(&graph, &RevisitPolicy::OneCave(0));
+            let one_twice = count_paths_with_policy(&graph, &RevisitPolicy::OneCave(1));
+            let total_one = count_paths_with_policy(&graph, &RevisitPolicy::Total(1));
+            let no_limits =
+                count_paths_with_policy(&graph, &RevisitPolicy::PerCave(HashMap::new()));
+
+            // then
+            assert_eq!(once, count_paths(&graph, true));
+            assert_eq!(one_twice, count_paths(&graph, false));
+            assert_eq!(total_one, count_paths(&graph, false));
+            assert_eq!(no_limits, count_paths(&graph, true));
+        }
+    }
+
+    #[test]
+    fn count_paths_with_policy_works_for_multiple_revisits() {
+        // given
+        let edges = parse_edges("start-A\nA-b\nA-end\n").expect("Expected successful parsing");
+        let graph =
+            CaveGraph::new(&edges, START_VERTICE, END_VERTICE).expect("Expected valid cave graph");
+
+        // when
+        let one_cave_three_times = count_paths_with_policy(&graph, &RevisitPolicy::OneCave(2));
+        let total_two = count_paths_with_policy(&graph, &RevisitPolicy::Total(2));
+        let per_cave = count_paths_with_policy(
+            &graph,
+            &parse_policy("caves:b=4").expect("Expected valid policy"),
+        );
+
+        // then
+        // the only choice is how often to go to b and back
+        assert_eq!(one_cave_three_times, 4);
+        assert_eq!(total_two, 4);
+        assert_eq!(per_cave, 5);
+    }
+
+    #[test]
+    fn count_paths_with_policy_works_for_highest_limits() {
+        // given
+        let edges = parse_edges("start-B\nB-a\nB-end\n").expect("Expected successful parsing");
+        let graph =
+            CaveGraph::new(&edges, START_VERTICE, END_VERTICE).expect("Expected valid cave graph");
+
+        // when
+        let per_cave = count_paths_with_policy(
+            &graph,
+            &parse_policy("caves:a=255").expect("Expected valid policy"),
+        );
+        let total = count_paths_with_policy(
+            &graph,
+            &parse_policy("total:255").expect("Expected valid policy"),
+        );
+        let one_cave = count_paths_with_policy(
+            &graph,
+            &parse_policy("one:255").expect("Expected valid policy"),
+        );
+
+        // then
+        // a can be visited 0 to 255 times
+        assert_eq!(per_cave, 256);
+        // a can be visited 0 times, once, or revisited up to 255 times
+        assert_eq!(total, 257);
+        assert_eq!(one_cave, 257);
+    }
+
+    #[test]
+    fn paths_work_with_other_start_and_end() {
+        // given
+        let edges = parse_edges(SIMPLE_EXAMPLE).expect("Expected successful parsing");
+        let graph = CaveGraph::new(&edges, "c", "d").expect("Expected valid cave graph");
+
+        // when
+        let count = count_paths(&graph, true);
+        let paths = find_all_paths(&edges, vec!["c"], "d", true);
+
+        // then
+        assert_eq!(count, paths.len() as u64);
+        let path_set: HashSet<Vec<&str>> = paths.into_iter().collect();
+        let expected_paths: HashSet<Vec<&str>> = [
+            vec!["c", "A", "b", "d"],
+            vec!["c", "A", "start", "b", "d"],
+            vec!["c", "A", "end", "b", "d"],
+            vec!["c", "A", "start", "A", "b", "d"],
+            vec!["c", "A", "end", "A", "b", "d"],
+            vec!["c", "A", "start", "A", "end", "b", "d"],
+            vec!["c", "A", "end", "A", "start", "b", "d"],
+            vec!["c", "A", "start", "A", "end", "A", "b", "d"],
+            vec!["c", "A", "end", "A", "start", "A", "b", "d"],
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(path_set, expected_paths);
+    }
+
+    #[test]
+    fn parse_policy_rejects_unknown_policies() {
+        assert!(parse_policy("sometimes:2").is_err());
+        assert!(parse_policy("one").is_err());
+        assert!(parse_policy("caves:a").is_err());
+    }
+
+    #[test]
+    fn edges_to_dot_highlights_path() {
+        // given
+        let edges = parse_edges("start-A\nA-b\nA-end\n").expect("Expected successful parsing");
+        let path = vec!["start", "A", "b", "A", "end"];
+
+        // when
+        let dot = edges_to_dot(&edges, "start", "end", &Highlight::Path(path));
+
+        // then
+        assert_eq!(
+            dot,
+            r#"graph caves {
+    "A" [shape=box, style=filled, fillcolor=lightgrey];
+    "b" [shape=ellipse];
+    "end" [shape=ellipse, peripheries=2];
+    "start" [shape=ellipse, peripheries=2];
+    "A" -- "b" [color=red, penwidth=3, label="2"];
+    "A" -- "end" [color=red, penwidth=3, label="1"];
+    "A" -- "start" [color=red, penwidth=3, label="1"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn edge_usage_counts_edges_in_both_directions() {
+        // given
+        let edges = parse_edges("start-A\nA-b\nA-end\n").expect("Expected successful parsing");
+        let paths = find_all_paths(&edges, vec![START_VERTICE], END_VERTICE, true);
+
+        // when
+        let usage = edge_usage(&paths);
+
+        // then
+        assert_eq!(usage.get(&("A", "start")), Some(&2));
+        assert_eq!(usage.get(&("A", "b")), Some(&2));
+        assert_eq!(usage.get(&("A", "end")), Some(&2));
+    }
 }
@@ -1,14 +1,15 @@
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::read_to_string;
+use std::fmt;
+use std::fs::{read_to_string, write};
 use std::path::Path;
 
 fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
 
     let (initial_dots, folding_instructions) = parse(&content)?;
 
@@ -22,73 +23,492 @@ fn main() -> Result<(), String> {
     }
 
     // puzzle 2
-    let final_dots = fold_dots(initial_dots, &folding_instructions);
-    print_dots(&final_dots);
+    let final_dots = fold_dots(initial_dots.clone(), &folding_instructions);
+    let glyph_table = GlyphTable::standard();
+    let letters = recognise_letters(&final_dots, &glyph_table);
+    let code: String = letters
+        .iter()
+        .map(|letter| letter.as_ref().copied().unwrap_or('?'))
+        .collect();
+    println!("The code is {}", code);
+    for (i, letter) in letters.iter().enumerate() {
+        if let Err(glyph) = letter {
+            println!("Unknown glyph at position {}:\n{}", i + 1, glyph);
+        }
+    }
+    if letters.iter().any(|letter| letter.is_err()) {
+        print_dots(&final_dots);
+    }
+
+    let mut show_steps = false;
+    let mut n_unfoldings: Option<usize> = None;
+    let mut svg_directory: Option<String> = None;
+    for arg in env::args().skip(2) {
+        if arg == "--steps" {
+            show_steps = true;
+        } else if let Some(directory) = arg.strip_prefix("--svg=") {
+            svg_directory = Some(directory.to_owned());
+        } else if let Some(n) = arg.strip_prefix("--unfold=") {
+            n_unfoldings = Some(
+                n.parse()
+                    .map_err(|e| format!("Unable to parse number of unfoldings: {}", e))?,
+            );
+        } else {
+            return Err(format!("Unknown option: {}", arg));
+        }
+    }
+    if let (Some(n), Some(last_fold)) = (n_unfoldings, folding_instructions.last()) {
+        println!("Possible sheets before {}:", last_fold);
+        for (i, sheet) in unfold(&final_dots, last_fold).take(n).enumerate() {
+            println!("#{}:\n{}", i + 1, render_ascii(&sheet));
+        }
+    }
+
+    if show_steps || svg_directory.is_some() {
+        let initial_state = (None, initial_dots.clone());
+        let steps = std::iter::once(initial_state).chain(
+            fold_steps(initial_dots, &folding_instructions).map(|(fold, dots)| (Some(fold), dots)),
+        );
+        for (i, (fold, dots)) in steps.enumerate() {
+            if show_steps {
+                match fold {
+                    Some(fold) => println!("After {}:", fold),
+                    None => println!("Before folding:"),
+                }
+                match bounding_box(&dots) {
+                    Some(bb) => println!("{} dots within {}", dots.len(), bb),
+                    None => println!("no dots"),
+                }
+                print!("{}", render_ascii(&dots));
+            }
+            if let Some(directory) = &svg_directory {
+                let next_fold = folding_instructions.get(i);
+                let path = Path::new(directory).join(format!("step_{:02}.svg", i));
+                write(&path, render_svg(&dots, next_fold)).map_err(|e| e.to_string())?;
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn fold_dots(mut dots: HashSet<Dot>, instructions: &[Fold]) -> HashSet<Dot> {
-    for fold in instructions {
-        let fold_fn = match fold.orientation {
-            Orientation::Horizontal => fold_vertical,
-            Orientation::Vertical => fold_horizontal,
-        };
-        // allocating a new set each time? Makes it simpler, and we don't fold _that_ often
-        dots = dots
-            .into_iter()
-            .filter_map(|dot| fold_fn(dot, fold.position))
-            .collect();
+fn fold_dots(dots: HashSet<Dot>, instructions: &[Fold]) -> HashSet<Dot> {
+    instructions.iter().fold(dots, fold_once)
+}
+
+fn fold_once(dots: HashSet<Dot>, fold: &Fold) -> HashSet<Dot> {
+    // allocating a new set each time? Makes it simpler, and we don't fold _that_ often
+    dots.into_iter()
+        .filter_map(|dot| fold_dot(dot, fold))
+        .collect()
+}
+
+// Iterates over the folds and the dots right after each fold.
+struct FoldSteps<'a> {
+    dots: HashSet<Dot>,
+    instructions: std::slice::Iter<'a, Fold>,
+}
+
+fn fold_steps(dots: HashSet<Dot>, instructions: &[Fold]) -> FoldSteps<'_> {
+    FoldSteps {
+        dots,
+        instructions: instructions.iter(),
     }
-    dots
 }
 
-fn fold_vertical((x, y): Dot, pos: i64) -> Option<Dot> {
-    match y.cmp(&pos) {
-        Ordering::Less => Some((x, y)),
-        Ordering::Greater => Some((x, -y + pos * 2)),
-        Ordering::Equal => None,
+impl<'a> Iterator for FoldSteps<'a> {
+    type Item = (&'a Fold, HashSet<Dot>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let fold = self.instructions.next()?;
+        self.dots = fold_once(std::mem::take(&mut self.dots), fold);
+        Some((fold, self.dots.clone()))
     }
 }
 
-fn fold_horizontal((x, y): Dot, pos: i64) -> Option<Dot> {
-    match x.cmp(&pos) {
-        Ordering::Less => Some((x, y)),
-        Ordering::Greater => Some((-x + pos * 2, y)),
+// Dots on the `Greater` side of the fold line are mirrored onto the `Less` side.
+fn fold_side((x, y): Dot, fold: &Fold) -> Ordering {
+    let coordinate = match fold.orientation {
+        Orientation::Horizontal => y,
+        Orientation::Vertical => x,
+        Orientation::Diagonal => x - y,
+        Orientation::AntiDiagonal => x + y,
+    };
+    coordinate.cmp(&fold.position)
+}
+
+fn mirror((x, y): Dot, fold: &Fold) -> Dot {
+    let pos = fold.position;
+    match fold.orientation {
+        Orientation::Horizontal => (x, -y + pos * 2),
+        Orientation::Vertical => (-x + pos * 2, y),
+        Orientation::Diagonal => (y + pos, x - pos),
+        Orientation::AntiDiagonal => (pos - y, pos - x),
+    }
+}
+
+// dots on the fold line disappear
+fn fold_dot(dot: Dot, fold: &Fold) -> Option<Dot> {
+    match fold_side(dot, fold) {
+        Ordering::Less => Some(dot),
+        Ordering::Greater => Some(mirror(dot, fold)),
         Ordering::Equal => None,
     }
 }
 
-fn print_dots(dots: &HashSet<Dot>) {
-    if dots.is_empty() {
-        return;
+// Iterates over all dot sets that result in the given dots when folded. Each dot may have been
+// there before, or it may have been folded over, or both. So for n dots, there are 3^n
+// possibilities. Dots that were on the fold line are lost for good, so they are not considered.
+struct Unfoldings {
+    dots: Vec<Dot>,
+    mirrored: Vec<Dot>,
+    // for each dot: 0 for the dot itself, 1 for the mirrored dot, 2 for both. `None` when done.
+    choices: Option<Vec<u8>>,
+}
+
+fn unfold(dots: &HashSet<Dot>, fold: &Fold) -> Unfoldings {
+    let mut dots: Vec<Dot> = dots.iter().copied().collect();
+    dots.sort_unstable();
+    // dots on the fold line or on the wrong side can not be the result of this fold
+    let choices = if dots
+        .iter()
+        .all(|dot| fold_side(*dot, fold) == Ordering::Less)
+    {
+        Some(vec![0; dots.len()])
+    } else {
+        None
+    };
+    Unfoldings {
+        mirrored: dots.iter().map(|dot| mirror(*dot, fold)).collect(),
+        dots,
+        choices,
     }
-    let min_x: i64 = dots.iter().map(|(x, _)| x).copied().min().unwrap();
-    let min_y: i64 = dots.iter().map(|(_, y)| y).copied().min().unwrap();
-    let max_x: i64 = dots.iter().map(|(x, _)| x).copied().max().unwrap();
-    let max_y: i64 = dots.iter().map(|(_, y)| y).copied().max().unwrap();
+}
+
+impl Iterator for Unfoldings {
+    type Item = HashSet<Dot>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let choices = self.choices.as_mut()?;
+        let mut result: HashSet<Dot> = HashSet::with_capacity(self.dots.len() * 2);
+        for ((dot, mirrored), choice) in self.dots.iter().zip(&self.mirrored).zip(choices.iter()) {
+            if *choice != 1 {
+                result.insert(*dot);
+            }
+            if *choice != 0 {
+                result.insert(*mirrored);
+            }
+        }
 
-    let width: usize = (max_x - min_x) as usize + 1;
-    let height: usize = (max_y - min_y) as usize + 1;
-    let mut dot_map: Vec<bool> = vec![false; width * height];
+        // count up in base 3
+        let mut overflow = true;
+        for choice in choices.iter_mut() {
+            if *choice < 2 {
+                *choice += 1;
+                overflow = false;
+                break;
+            }
+            *choice = 0;
+        }
+        if overflow {
+            self.choices = None;
+        }
+        Some(result)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct BoundingBox {
+    min_x: i64,
+    min_y: i64,
+    max_x: i64,
+    max_y: i64,
+}
+
+impl BoundingBox {
+    fn width(&self) -> i64 {
+        self.max_x - self.min_x + 1
+    }
+
+    fn height(&self) -> i64 {
+        self.max_y - self.min_y + 1
+    }
+}
+
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "x={}..{}, y={}..{} ({}x{})",
+            self.min_x,
+            self.max_x,
+            self.min_y,
+            self.max_y,
+            self.width(),
+            self.height()
+        )
+    }
+}
+
+fn bounding_box(dots: &HashSet<Dot>) -> Option<BoundingBox> {
+    Some(BoundingBox {
+        min_x: dots.iter().map(|(x, _)| x).copied().min()?,
+        min_y: dots.iter().map(|(_, y)| y).copied().min()?,
+        max_x: dots.iter().map(|(x, _)| x).copied().max()?,
+        max_y: dots.iter().map(|(_, y)| y).copied().max()?,
+    })
+}
+
+fn render_dots(dots: &HashSet<Dot>, dot: char, empty: char) -> String {
+    let bb = match bounding_box(dots) {
+        Some(bb) => bb,
+        None => return String::new(),
+    };
+    let width = bb.width() as usize;
+    let mut dot_map: Vec<bool> = vec![false; width * bb.height() as usize];
 
     for (x, y) in dots {
-        dot_map[(x - min_x) as usize + (y - min_y) as usize * width] = true;
+        dot_map[(x - bb.min_x) as usize + (y - bb.min_y) as usize * width] = true;
     }
 
-    // this won't print fast, but this is no bottleneck, so who cares?
+    let mut result = String::with_capacity((width + 1) * bb.height() as usize);
     for row in dot_map.chunks(width) {
         for col in row {
-            if *col {
-                print!("█");
-            } else {
-                print!(" ");
+            result.push(if *col { dot } else { empty });
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn print_dots(dots: &HashSet<Dot>) {
+    print!("{}", render_dots(dots, '█', ' '));
+}
+
+fn render_ascii(dots: &HashSet<Dot>) -> String {
+    render_dots(dots, '#', '.')
+}
+
+// One square per dot. If there is a next fold, its line is drawn in red, so it is easy to see
+// whether the fold is in the middle of the paper.
+fn render_svg(dots: &HashSet<Dot>, next_fold: Option<&Fold>) -> String {
+    let bb = bounding_box(dots).unwrap_or(BoundingBox {
+        min_x: 0,
+        min_y: 0,
+        max_x: 0,
+        max_y: 0,
+    });
+    let (min_x, max_x, min_y, max_y) = match next_fold {
+        Some(Fold {
+            orientation: Orientation::Vertical,
+            position,
+        }) => (
+            bb.min_x.min(*position),
+            bb.max_x.max(*position),
+            bb.min_y,
+            bb.max_y,
+        ),
+        Some(Fold {
+            orientation: Orientation::Horizontal,
+            position,
+        }) => (
+            bb.min_x,
+            bb.max_x,
+            bb.min_y.min(*position),
+            bb.max_y.max(*position),
+        ),
+        _ => (bb.min_x, bb.max_x, bb.min_y, bb.max_y),
+    };
+    let mut sorted_dots: Vec<&Dot> = dots.iter().collect();
+    sorted_dots.sort_unstable();
+
+    let mut result = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min_x,
+        min_y,
+        max_x - min_x + 1,
+        max_y - min_y + 1
+    );
+    for (x, y) in sorted_dots {
+        result.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\"/>\n",
+            x, y
+        ));
+    }
+    match next_fold {
+        Some(Fold {
+            orientation: Orientation::Vertical,
+            position,
+        }) => result.push_str(&format!(
+            "  <line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"red\" stroke-width=\"0.2\"/>\n",
+            center(*position),
+            min_y,
+            max_y + 1
+        )),
+        Some(Fold {
+            orientation: Orientation::Horizontal,
+            position,
+        }) => result.push_str(&format!(
+            "  <line x1=\"{1}\" y1=\"{0}\" x2=\"{2}\" y2=\"{0}\" stroke=\"red\" stroke-width=\"0.2\"/>\n",
+            center(*position),
+            min_x,
+            max_x + 1
+        )),
+        // x - y = c, through the centers of the squares
+        Some(Fold {
+            orientation: Orientation::Diagonal,
+            position,
+        }) => result.push_str(&format!(
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"red\" stroke-width=\"0.2\"/>\n",
+            center(min_x),
+            center(min_x - position),
+            center(max_x),
+            center(max_x - position)
+        )),
+        // x + y = c
+        Some(Fold {
+            orientation: Orientation::AntiDiagonal,
+            position,
+        }) => result.push_str(&format!(
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"red\" stroke-width=\"0.2\"/>\n",
+            center(min_x),
+            center(position - min_x),
+            center(max_x),
+            center(position - max_x)
+        )),
+        None => (),
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+// the middle of the square at this coordinate, also for negative ones ("-1.5" is not -1 + 0.5)
+fn center(coordinate: i64) -> f64 {
+    coordinate as f64 + 0.5
+}
+
+const GLYPH_WIDTH: i64 = 4;
+const GLYPH_HEIGHT: i64 = 6;
+// glyphs are separated by one empty column
+const GLYPH_SPACING: i64 = GLYPH_WIDTH + 1;
+
+// one bit per pixel, row by row, starting with the top left pixel in the lowest bit
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+struct Glyph(u32);
+
+impl Glyph {
+    fn from_pattern(pattern: &str) -> Result<Glyph, String> {
+        let rows: Vec<&str> = pattern.lines().collect();
+        if rows.len() != GLYPH_HEIGHT as usize
+            || rows
+                .iter()
+                .any(|row| row.chars().count() != GLYPH_WIDTH as usize)
+        {
+            return Err(format!(
+                "Expected a {}x{} glyph pattern, got:\n{}",
+                GLYPH_WIDTH, GLYPH_HEIGHT, pattern
+            ));
+        }
+        rows.iter()
+            .flat_map(|row| row.chars())
+            .enumerate()
+            .try_fold(Glyph(0), |glyph, (i, c)| match c {
+                '#' => Ok(Glyph(glyph.0 | (1 << i))),
+                '.' => Ok(glyph),
+                other => Err(format!("Unexpected character '{}' in glyph pattern", other)),
+            })
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..GLYPH_HEIGHT {
+            for column in 0..GLYPH_WIDTH {
+                if self.0 & (1 << (row * GLYPH_WIDTH + column)) != 0 {
+                    write!(f, "█")?;
+                } else {
+                    write!(f, " ")?;
+                }
             }
+            writeln!(f)?;
         }
-        println!();
+        Ok(())
     }
 }
 
+const STANDARD_GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...\n#...\n.#.#\n..#.\n..#.\n..#."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+struct GlyphTable {
+    glyphs: HashMap<Glyph, char>,
+}
+
+impl GlyphTable {
+    fn standard() -> GlyphTable {
+        let mut table = GlyphTable {
+            glyphs: HashMap::with_capacity(STANDARD_GLYPHS.len()),
+        };
+        for (letter, pattern) in STANDARD_GLYPHS {
+            table
+                .add(letter, pattern)
+                .expect("Expected valid standard glyph patterns");
+        }
+        table
+    }
+
+    fn add(&mut self, letter: char, pattern: &str) -> Result<(), String> {
+        let glyph = Glyph::from_pattern(pattern)?;
+        if let Some(other) = self.glyphs.get(&glyph) {
+            return Err(format!(
+                "'{}' and '{}' have the same glyph pattern",
+                other, letter
+            ));
+        }
+        self.glyphs.insert(glyph, letter);
+        Ok(())
+    }
+}
+
+// Reads the letters from left to right, assuming the first letter starts at x=0 and the letters
+// are in the top six rows. Unknown glyphs are returned as they are.
+fn recognise_letters(dots: &HashSet<Dot>, table: &GlyphTable) -> Vec<Result<char, Glyph>> {
+    let max_x = match dots.iter().map(|(x, _)| *x).max() {
+        Some(max_x) if max_x >= 0 => max_x,
+        _ => return vec![],
+    };
+    let n_letters = max_x / GLYPH_SPACING + 1;
+    let mut glyphs: Vec<Glyph> = vec![Glyph(0); n_letters as usize];
+    for (x, y) in dots {
+        let column = x % GLYPH_SPACING;
+        if *x < 0 || *y < 0 || *y >= GLYPH_HEIGHT || column >= GLYPH_WIDTH {
+            continue;
+        }
+        glyphs[(x / GLYPH_SPACING) as usize].0 |= 1 << (y * GLYPH_WIDTH + column);
+    }
+    glyphs
+        .into_iter()
+        .map(|glyph| table.glyphs.get(&glyph).copied().ok_or(glyph))
+        .collect()
+}
+
 fn parse(content: &str) -> Result<(HashSet<Dot>, Vec<Fold>), String> {
     let (dot_content, fold_content) = content.split_once("\n\n").ok_or_else(|| {
         "Unable to find separator between dots and folding instructions".to_owned()
@@ -124,6 +544,10 @@ fn parse_dot(line: &str) -> Result<Dot, String> {
 enum Orientation {
     Horizontal,
     Vertical,
+    // along x - y = position
+    Diagonal,
+    // along x + y = position
+    AntiDiagonal,
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
@@ -132,26 +556,36 @@ struct Fold {
     position: i64,
 }
 
+impl fmt::Display for Fold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.orientation {
+            Orientation::Vertical => write!(f, "fold along x={}", self.position),
+            Orientation::Horizontal => write!(f, "fold along y={}", self.position),
+            Orientation::Diagonal => write!(f, "fold along x-y={}", self.position),
+            Orientation::AntiDiagonal => write!(f, "fold along x+y={}", self.position),
+        }
+    }
+}
+
 fn parse_fold(line: &str) -> Result<Fold, String> {
-    if let Some(s) = line.strip_prefix("fold along x=") {
-        let position = s
-            .parse::<i64>()
-            .map_err(|e| format!("unable to parse position for fold '{}': {}", line, e))?;
-        Ok(Fold {
-            orientation: Orientation::Vertical,
-            position,
-        })
+    let (orientation, s) = if let Some(s) = line.strip_prefix("fold along x=") {
+        (Orientation::Vertical, s)
     } else if let Some(s) = line.strip_prefix("fold along y=") {
-        let position = s
-            .parse::<i64>()
-            .map_err(|e| format!("unable to parse position for fold '{}': {}", line, e))?;
-        Ok(Fold {
-            orientation: Orientation::Horizontal,
-            position,
-        })
+        (Orientation::Horizontal, s)
+    } else if let Some(s) = line.strip_prefix("fold along x-y=") {
+        (Orientation::Diagonal, s)
+    } else if let Some(s) = line.strip_prefix("fold along x+y=") {
+        (Orientation::AntiDiagonal, s)
     } else {
-        Err(format!("unable to parse fold '{}'", line))
-    }
+        return Err(format!("unable to parse fold '{}'", line));
+    };
+    let position = s
+        .parse::<i64>()
+        .map_err(|e| format!("unable to parse position for fold '{}': {}", line, e))?;
+    Ok(Fold {
+        orientation,
+        position,
+    })
 }
 
 #[cfg(test)]
@@ -193,4 +627,273 @@ fold along x=5
         print_dots(&result);
         assert_eq!(result.len(), 16);
     }
+
+    #[test]
+    fn fold_steps_works_for_example() {
+        // given
+        let (dots, instructions) = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+
+        // when
+        let steps: Vec<(&Fold, HashSet<Dot>)> = fold_steps(dots, &instructions).collect();
+
+        // then
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].0, &instructions[0]);
+        assert_eq!(steps[0].1.len(), 17);
+        assert_eq!(
+            bounding_box(&steps[0].1),
+            Some(BoundingBox {
+                min_x: 0,
+                min_y: 0,
+                max_x: 10,
+                max_y: 4
+            })
+        );
+        assert_eq!(
+            render_ascii(&steps[1].1),
+            "#####\n#...#\n#...#\n#...#\n#####\n"
+        );
+    }
+
+    #[test]
+    fn render_svg_draws_dots_and_next_fold() {
+        // given
+        let dots: HashSet<Dot> = [(0, 0), (3, 1)].into_iter().collect();
+        let fold = Fold {
+            orientation: Orientation::Vertical,
+            position: 1,
+        };
+
+        // when
+        let svg = render_svg(&dots, Some(&fold));
+
+        // then
+        assert_eq!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 2">
+  <rect x="0" y="0" width="1" height="1"/>
+  <rect x="3" y="1" width="1" height="1"/>
+  <line x1="1.5" y1="0" x2="1.5" y2="2" stroke="red" stroke-width="0.2"/>
+</svg>
+"#
+        );
+    }
+
+    #[test]
+    fn render_svg_draws_fold_at_negative_position() {
+        // given
+        let dots: HashSet<Dot> = [(0, 0), (1, 0)].into_iter().collect();
+        let fold = Fold {
+            orientation: Orientation::Horizontal,
+            position: -1,
+        };
+
+        // when
+        let svg = render_svg(&dots, Some(&fold));
+
+        // then
+        assert_eq!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 -1 2 2">
+  <rect x="0" y="0" width="1" height="1"/>
+  <rect x="1" y="0" width="1" height="1"/>
+  <line x1="0" y1="-0.5" x2="2" y2="-0.5" stroke="red" stroke-width="0.2"/>
+</svg>
+"#
+        );
+    }
+
+    #[test]
+    fn render_svg_draws_diagonal_fold_with_negative_endpoint() {
+        // given
+        let dots: HashSet<Dot> = [(0, 0), (3, 0)].into_iter().collect();
+        let fold = parse_fold("fold along x-y=1").expect("Expected successful parsing");
+
+        // when
+        let svg = render_svg(&dots, Some(&fold));
+
+        // then
+        // the line starts at (0, -1) and ends at (3, 2)
+        assert_eq!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 1">
+  <rect x="0" y="0" width="1" height="1"/>
+  <rect x="3" y="0" width="1" height="1"/>
+  <line x1="0.5" y1="-0.5" x2="3.5" y2="2.5" stroke="red" stroke-width="0.2"/>
+</svg>
+"#
+        );
+    }
+
+    #[test]
+    fn fold_dots_works_for_diagonal_folds() {
+        // given
+        let (dots, instructions) =
+            parse("0,0\n2,0\n2,1\n0,2\n\nfold along x-y=1\nfold along x+y=2\n")
+                .expect("Expected successful parsing");
+
+        // when
+        let after_diagonal = fold_dots(dots.clone(), &instructions[0..1]);
+        let after_both = fold_dots(dots, &instructions);
+
+        // then
+        // (2, 1) is on the line, (2, 0) is mirrored to (1, 1)
+        let expected: HashSet<Dot> = [(0, 0), (1, 1), (0, 2)].into_iter().collect();
+        assert_eq!(after_diagonal, expected);
+        // (1, 1) and (0, 2) are on the line
+        let expected: HashSet<Dot> = [(0, 0)].into_iter().collect();
+        assert_eq!(after_both, expected);
+    }
+
+    #[test]
+    fn parse_fold_works_for_all_orientations() {
+        for line in [
+            "fold along x=5",
+            "fold along y=7",
+            "fold along x-y=-3",
+            "fold along x+y=12",
+        ] {
+            let fold = parse_fold(line).expect("Expected successful parsing");
+            assert_eq!(fold.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn unfold_enumerates_all_preimages() {
+        // given
+        let dots: HashSet<Dot> = [(0, 0), (1, 2)].into_iter().collect();
+        let fold = Fold {
+            orientation: Orientation::Vertical,
+            position: 2,
+        };
+
+        // when
+        let preimages: Vec<HashSet<Dot>> = unfold(&dots, &fold).collect();
+
+        // then
+        assert_eq!(preimages.len(), 9);
+        for preimage in &preimages {
+            assert_eq!(
+                fold_dots(preimage.clone(), std::slice::from_ref(&fold)),
+                dots
+            );
+        }
+        let expected: HashSet<Dot> = [(4, 0), (0, 0), (3, 2)].into_iter().collect();
+        assert!(preimages.contains(&expected));
+    }
+
+    #[test]
+    fn unfold_works_for_diagonal_folds() {
+        // given
+        let dots: HashSet<Dot> = [(0, 1)].into_iter().collect();
+        let fold = parse_fold("fold along x+y=3").expect("Expected successful parsing");
+
+        // when
+        let preimages: Vec<HashSet<Dot>> = unfold(&dots, &fold).collect();
+
+        // then
+        assert_eq!(
+            preimages,
+            vec![
+                [(0, 1)].into_iter().collect(),
+                [(2, 3)].into_iter().collect(),
+                [(0, 1), (2, 3)].into_iter().collect(),
+            ]
+        );
+    }
+
+    #[test]
+    fn unfold_finds_nothing_for_dots_on_the_fold_line() {
+        // given
+        let dots: HashSet<Dot> = [(2, 0)].into_iter().collect();
+        let fold = parse_fold("fold along x=2").expect("Expected successful parsing");
+
+        // when
+        let n_preimages = unfold(&dots, &fold).count();
+
+        // then
+        assert_eq!(n_preimages, 0);
+    }
+
+    fn dots_from_picture(picture: &str) -> HashSet<Dot> {
+        picture
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(x, _)| (x as i64, y as i64))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recognise_letters_works_for_known_letters() {
+        // given
+        let dots = dots_from_picture(
+            r"#..#.####..##..###.
+#..#.#....#..#.#..#
+####.###..#..#.#..#
+#..#.#....#..#.###.
+#..#.#....#..#.#.#.
+#..#.####..##..#..#
+",
+        );
+
+        // when
+        let letters = recognise_letters(&dots, &GlyphTable::standard());
+
+        // then
+        assert_eq!(letters, vec![Ok('H'), Ok('E'), Ok('O'), Ok('R')]);
+    }
+
+    #[test]
+    fn recognise_letters_returns_unknown_glyphs() {
+        // given
+        let dots =
+            dots_from_picture("####.#...\n.....#...\n.....#...\n.....#...\n.....#...\n.....####\n");
+
+        // when
+        let letters = recognise_letters(&dots, &GlyphTable::standard());
+
+        // then
+        assert_eq!(
+            letters,
+            vec![
+                Err(Glyph::from_pattern("####\n....\n....\n....\n....\n....").unwrap()),
+                Ok('L')
+            ]
+        );
+    }
+
+    #[test]
+    fn glyph_table_can_be_extended() {
+        // given
+        let mut table = GlyphTable::standard();
+        let dots = dots_from_picture("####\n");
+
+        // when
+        let added = table.add('-', "####\n....\n....\n....\n....\n....");
+        let duplicate = table.add('=', "####\n....\n....\n....\n....\n....");
+
+        // then
+        assert_eq!(added, Ok(()));
+        assert!(duplicate.is_err());
+        assert_eq!(recognise_letters(&dots, &table), vec![Ok('-')]);
+    }
+
+    #[test]
+    fn recognise_letters_works_for_example() {
+        // given
+        let (dots, instructions) = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+
+        // when
+        let letters = recognise_letters(&fold_dots(dots, &instructions), &GlyphTable::standard());
+
+        // then
+        // the example is a square, and not a letter
+        assert_eq!(letters.len(), 1);
+        assert!(letters[0].is_err());
+    }
 }
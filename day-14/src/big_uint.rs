@@ -0,0 +1,220 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+// Just enough of an arbitrary precision unsigned integer to count polymer elements.
+// Digits are stored as base 2^32 limbs, least significant limb first, without trailing zero limbs.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn normalize(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    // divides in place by a small divisor and returns the remainder
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut rem: u64 = 0;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (rem << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            rem = current % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> BigUint {
+        BigUint {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        }
+        .normalize()
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (&self.limbs, &other.limbs)
+        } else {
+            (&other.limbs, &self.limbs)
+        };
+        let mut limbs: Vec<u32> = Vec::with_capacity(long.len() + 1);
+        let mut carry: u64 = 0;
+        for (i, l) in long.iter().enumerate() {
+            let sum = *l as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry != 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    // panics if the result would be negative, just like the primitive types do in debug builds
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(
+            self.limbs.len() >= other.limbs.len(),
+            "attempt to subtract with overflow"
+        );
+        let mut limbs: Vec<u32> = Vec::with_capacity(self.limbs.len());
+        let mut borrow: i64 = 0;
+        for (i, l) in self.limbs.iter().enumerate() {
+            let mut difference =
+                *l as i64 - other.limbs.get(i).copied().unwrap_or(0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(difference as u32);
+        }
+        assert!(borrow == 0, "attempt to subtract with overflow");
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        // without trailing zero limbs, more limbs means a larger number
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        // plain schoolbook multiplication, good enough for a few thousand limbs
+        let mut limbs: Vec<u32> = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let current = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = current as u32;
+                carry = current >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // collect chunks of nine decimal digits, least significant first
+        const CHUNK: u32 = 1_000_000_000;
+        let mut rest = self.clone();
+        let mut chunks: Vec<u32> = Vec::with_capacity(self.limbs.len() * 10 / 9 + 1);
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(CHUNK));
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn add_works_with_carry() {
+        // given
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(1);
+
+        // when
+        let sum = &a + &b;
+
+        // then
+        assert_eq!(sum.to_string(), "18446744073709551616");
+    }
+
+    #[test]
+    fn mul_works_beyond_u64() {
+        // given
+        let a = BigUint::from(u64::MAX);
+
+        // when
+        let product = &a * &a;
+
+        // then
+        assert_eq!(
+            product.to_string(),
+            "340282366920938463426481119284349108225"
+        );
+    }
+
+    #[test]
+    fn sub_works_with_borrow() {
+        // given
+        let a = &BigUint::from(u64::MAX) + &BigUint::from(1);
+        let b = BigUint::from(1);
+
+        // when
+        let difference = &a - &b;
+
+        // then
+        assert_eq!(difference, BigUint::from(u64::MAX));
+        assert_eq!(&a - &a, BigUint::zero());
+    }
+
+    #[test]
+    fn cmp_compares_most_significant_limbs_first() {
+        assert!(BigUint::from(1 << 32) > BigUint::from(u32::MAX as u64));
+        assert!(BigUint::from((1 << 32) + 1) > BigUint::from(1 << 32));
+        assert!(BigUint::zero() < BigUint::from(1));
+    }
+
+    #[test]
+    fn display_works_for_small_numbers() {
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(1_000_000_007).to_string(), "1000000007");
+    }
+}
@@ -1,98 +1,455 @@
-use std::collections::HashMap;
+mod big_uint;
+
+use big_uint::BigUint;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs::read_to_string;
+use std::ops::Range;
 use std::path::Path;
 
 fn main() -> Result<(), String> {
-    let filename = env::args()
-        .nth(1)
+    let mut args = env::args().skip(1);
+    let filename = args
+        .next()
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let mut n_steps: u64 = 40;
+    let mut engine = Engine::Stepwise;
+    let mut show_histogram = false;
+    let mut slice: Option<Range<u64>> = None;
+    let mut validation = Validation::Lenient;
+    for arg in args {
+        if let Some(n) = arg.strip_prefix("--steps=") {
+            n_steps = n
+                .parse()
+                .map_err(|e| format!("Unable to parse number of steps: {}", e))?;
+        } else if arg == "--matrix" {
+            engine = Engine::MatrixPower;
+        } else if arg == "--histogram" {
+            show_histogram = true;
+        } else if arg == "--strict" {
+            validation = Validation::Strict;
+        } else if let Some(range) = arg.strip_prefix("--slice=") {
+            slice = Some(parse_range(range)?);
+        } else {
+            return Err(format!("Unknown argument '{}'", arg));
+        }
+    }
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
 
-    let (template, rules) = parse(&content)?;
+    let (template, rules, issues) = parse(&content, validation)?;
+    for issue in &issues {
+        println!("Warning: {}", issue);
+    }
+
+    // before the histograms, so that too many steps for a slice are reported right away
+    let expansion = match slice {
+        Some(_) => Some(Expansion::new(&template, &rules, n_steps)?),
+        None => None,
+    };
 
-    let after_10_steps = grow_steps(template.clone(), &rules, 10);
-    if let Some(score) = score(&after_10_steps) {
+    let after_10_steps = histogram_after_steps(&template, &rules, 10, Engine::Stepwise);
+    if let Some(score) = score_histogram(&after_10_steps) {
         println!("most common - least common: {}", score);
     } else {
         println!("I accidentally a polymer is this dangerous?");
     }
 
-    if let Some(score_after_40_steps) = score_after_steps(&template, &rules, 40) {
+    let histogram = histogram_after_steps(&template, &rules, n_steps, engine);
+    if let Some(score) = score_histogram(&histogram) {
         println!(
-            "most common - least common after 40 steps: {}",
-            score_after_40_steps
+            "most common - least common after {} steps: {}",
+            n_steps, score
         );
     } else {
         println!("There is no polymer.");
     }
+    if show_histogram {
+        for (element, count) in &histogram {
+            println!("{}: {}", element, count);
+        }
+    }
+
+    if let Some((range, expansion)) = slice.zip(expansion) {
+        match expansion.slice(range.clone()) {
+            Some(elements) => println!(
+                "elements {}..{} after {} steps: {}",
+                range.start,
+                range.end,
+                n_steps,
+                elements.iter().collect::<String>()
+            ),
+            None => println!(
+                "elements {}..{} are out of range, the polymer has {} elements after {} steps",
+                range.start,
+                range.end,
+                expansion.len(),
+                n_steps
+            ),
+        }
+    }
 
     Ok(())
 }
 
-fn score_after_steps(
-    template: &[char],
-    rules: &HashMap<(char, char), char>,
-    n_steps: u64,
-) -> Option<u64> {
-    let mut letter_counts: HashMap<char, u64> =
-        template
+type Histogram = BTreeMap<char, BigUint>;
+type Matrix = Vec<Vec<BigUint>>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Engine {
+    // one step after the other, O(steps * pairs)
+    Stepwise,
+    // exponentiation by squaring of the transition matrix, O(log(steps) * pairs^3)
+    MatrixPower,
+}
+
+// The pair counts of a polymer are kept in a dense vector, where the pair (a, b) is at
+// `index(a) * alphabet.len() + index(b)`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct PairMatrix {
+    alphabet: Vec<char>,
+    // for each pair: the two pairs it turns into, or None if no rule applies
+    successors: Vec<Option<(usize, usize)>>,
+}
+
+impl PairMatrix {
+    fn new(template: &[char], rules: &HashMap<(char, char), char>) -> PairMatrix {
+        let mut alphabet: Vec<char> = template
             .iter()
             .copied()
-            .fold(HashMap::with_capacity(template.len()), |mut lc, c| {
-                let counter = lc.entry(c).or_insert(0);
-                *counter += 1;
-                lc
-            });
-    let mut tuple_counts: HashMap<(char, char), u64> =
-        template
-            .windows(2)
-            .fold(HashMap::with_capacity(rules.len()), |mut tc, tuple| {
-                let counter = tc.entry((tuple[0], tuple[1])).or_insert(0);
-                *counter += 1;
-                tc
-            });
+            .chain(rules.iter().flat_map(|((a, b), c)| [*a, *b, *c]))
+            .collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        let n = alphabet.len();
+        let index = |c: &char| alphabet.binary_search(c).expect("alphabet is complete");
+        let successors = (0..n * n)
+            .map(|pair| {
+                let (a, b) = (pair / n, pair % n);
+                rules
+                    .get(&(alphabet[a], alphabet[b]))
+                    .map(|inserted| (a * n + index(inserted), index(inserted) * n + b))
+            })
+            .collect();
+        PairMatrix {
+            alphabet,
+            successors,
+        }
+    }
 
-    for _ in 0..n_steps {
-        let mut next_tuple_counts: HashMap<(char, char), u64> =
-            HashMap::with_capacity(tuple_counts.len() * 2);
-        for (tuple, tuple_count) in tuple_counts {
-            if let Some(inserted) = rules.get(&tuple).copied() {
-                let letter_count = letter_counts.entry(inserted).or_insert(0);
-                *letter_count += tuple_count;
-                let first_tuple_count = next_tuple_counts.entry((tuple.0, inserted)).or_insert(0);
-                *first_tuple_count += tuple_count;
-                let second_tuple_count = next_tuple_counts.entry((inserted, tuple.1)).or_insert(0);
-                *second_tuple_count += tuple_count;
-            } else {
-                next_tuple_counts.insert(tuple, tuple_count);
+    fn n_pairs(&self) -> usize {
+        self.successors.len()
+    }
+
+    fn pair_index(&self, a: char, b: char) -> Option<usize> {
+        let i = self.alphabet.binary_search(&a).ok()?;
+        let j = self.alphabet.binary_search(&b).ok()?;
+        Some(i * self.alphabet.len() + j)
+    }
+
+    fn pair_counts(&self, template: &[char]) -> Vec<BigUint> {
+        let mut counts = vec![BigUint::zero(); self.n_pairs()];
+        for pair in template.windows(2) {
+            let index = self
+                .pair_index(pair[0], pair[1])
+                .expect("template letters are part of the alphabet");
+            counts[index] = &counts[index] + &BigUint::from(1);
+        }
+        counts
+    }
+
+    fn step(&self, counts: &[BigUint]) -> Vec<BigUint> {
+        let mut next = vec![BigUint::zero(); self.n_pairs()];
+        for (pair, count) in counts.iter().enumerate() {
+            if count.is_zero() {
+                continue;
+            }
+            match self.successors[pair] {
+                Some((first, second)) => {
+                    next[first] = &next[first] + count;
+                    next[second] = &next[second] + count;
+                }
+                None => next[pair] = &next[pair] + count,
             }
         }
-        tuple_counts = next_tuple_counts;
+        next
     }
 
-    let min = letter_counts.values().min()?;
-    let max = letter_counts.values().max()?;
+    // the same as `step`, as a matrix where `next[i] = sum(m[i][j] * counts[j])`
+    fn transition_matrix(&self) -> Matrix {
+        let size = self.n_pairs();
+        let mut m: Matrix = vec![vec![BigUint::zero(); size]; size];
+        for (pair, successors) in self.successors.iter().enumerate() {
+            match successors {
+                Some((first, second)) => {
+                    m[*first][pair] = &m[*first][pair] + &BigUint::from(1);
+                    m[*second][pair] = &m[*second][pair] + &BigUint::from(1);
+                }
+                None => m[pair][pair] = BigUint::from(1),
+            }
+        }
+        m
+    }
 
-    Some(max - min)
+    fn pair_counts_after_steps(
+        &self,
+        template: &[char],
+        n_steps: u64,
+        engine: Engine,
+    ) -> Vec<BigUint> {
+        let counts = self.pair_counts(template);
+        match engine {
+            Engine::Stepwise => (0..n_steps).fold(counts, |counts, _| self.step(&counts)),
+            Engine::MatrixPower => {
+                let mut result = identity_matrix(self.n_pairs());
+                let mut power = self.transition_matrix();
+                let mut remaining_steps = n_steps;
+                while remaining_steps > 0 {
+                    if remaining_steps & 1 == 1 {
+                        result = mul_matrices(&result, &power);
+                    }
+                    remaining_steps >>= 1;
+                    if remaining_steps > 0 {
+                        power = mul_matrices(&power, &power);
+                    }
+                }
+                mul_matrix_vector(&result, &counts)
+            }
+        }
+    }
+
+    // Every element is the first one of exactly one pair, except for the last one. Insertions
+    // never happen at the end, so the last element of the template stays the last one.
+    fn histogram(&self, template: &[char], counts: &[BigUint]) -> Histogram {
+        let n = self.alphabet.len();
+        let mut histogram: Histogram = BTreeMap::new();
+        for (pair, count) in counts.iter().enumerate() {
+            if !count.is_zero() {
+                let entry = histogram
+                    .entry(self.alphabet[pair / n])
+                    .or_insert_with(BigUint::zero);
+                *entry = &*entry + count;
+            }
+        }
+        if let Some(last) = template.last() {
+            let entry = histogram.entry(*last).or_insert_with(BigUint::zero);
+            *entry = &*entry + &BigUint::from(1);
+        }
+        histogram
+    }
 }
 
-fn score(polymer: &[char]) -> Option<usize> {
-    let counts: HashMap<char, usize> =
-        polymer
-            .iter()
-            .copied()
-            .fold(HashMap::with_capacity(26), |mut counts, c| {
-                let counter = counts.entry(c).or_insert(0);
-                *counter += 1;
-                counts
-            });
-    let min = counts.values().min()?;
-    let max = counts.values().max()?;
+fn identity_matrix(size: usize) -> Matrix {
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| BigUint::from(if i == j { 1 } else { 0 }))
+                .collect()
+        })
+        .collect()
+}
+
+fn mul_matrices(a: &Matrix, b: &Matrix) -> Matrix {
+    a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|j| {
+                    row.iter()
+                        .zip(b.iter())
+                        .filter(|(x, b_row)| !x.is_zero() && !b_row[j].is_zero())
+                        .fold(BigUint::zero(), |sum, (x, b_row)| &sum + &(x * &b_row[j]))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn mul_matrix_vector(m: &Matrix, v: &[BigUint]) -> Vec<BigUint> {
+    m.iter()
+        .map(|row| {
+            row.iter()
+                .zip(v)
+                .filter(|(x, y)| !x.is_zero() && !y.is_zero())
+                .fold(BigUint::zero(), |sum, (x, y)| &sum + &(x * y))
+        })
+        .collect()
+}
+
+fn histogram_after_steps(
+    template: &[char],
+    rules: &HashMap<(char, char), char>,
+    n_steps: u64,
+    engine: Engine,
+) -> Histogram {
+    let pair_matrix = PairMatrix::new(template, rules);
+    let counts = pair_matrix.pair_counts_after_steps(template, n_steps, engine);
+    pair_matrix.histogram(template, &counts)
+}
+
+fn score_histogram(histogram: &Histogram) -> Option<BigUint> {
+    let min = histogram.values().min()?;
+    let max = histogram.values().max()?;
 
     Some(max - min)
 }
 
+// Answers questions about the polymer after a number of steps, without building it. Each pair of
+// the template expands independently, and `lengths[step][pair]` is the number of elements the pair
+// turns into after `step` steps, without its last element (which belongs to the next pair).
+// Depending on the rules, the lengths grow exponentially and saturate at u64::MAX after some dozen
+// steps, or they grow slower (AB -> A only adds one element per step) and keep changing. Once the
+// lengths stop changing, the last row is used for all further steps. Otherwise there is one row per
+// step, and the search in `collect` goes down one level per step, so the number of steps is limited.
+const MAX_SLICE_STEPS: u64 = 1_000_000;
+// 80 MB of lengths
+const MAX_LENGTH_ENTRIES: usize = 10_000_000;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Expansion {
+    pair_matrix: PairMatrix,
+    template: Vec<char>,
+    n_steps: u64,
+    lengths: Vec<Vec<u64>>,
+}
+
+impl Expansion {
+    fn new(
+        template: &[char],
+        rules: &HashMap<(char, char), char>,
+        n_steps: u64,
+    ) -> Result<Expansion, String> {
+        if n_steps > MAX_SLICE_STEPS {
+            return Err(format!(
+                "Slices are only supported up to {} steps",
+                MAX_SLICE_STEPS
+            ));
+        }
+        let pair_matrix = PairMatrix::new(template, rules);
+        let mut lengths: Vec<Vec<u64>> = vec![vec![1; pair_matrix.n_pairs()]];
+        let max_rows = MAX_LENGTH_ENTRIES / pair_matrix.n_pairs().max(1);
+        for _ in 0..n_steps {
+            if lengths.len() >= max_rows {
+                return Err(format!(
+                    "The polymer still grows slowly after {} steps, slices after {} steps would need too much memory",
+                    lengths.len() - 1,
+                    n_steps
+                ));
+            }
+            let previous = lengths.last().expect("there is always step 0");
+            let next: Vec<u64> = pair_matrix
+                .successors
+                .iter()
+                .enumerate()
+                .map(|(pair, successors)| match successors {
+                    Some((first, second)) => previous[*first].saturating_add(previous[*second]),
+                    None => previous[pair],
+                })
+                .collect();
+            if &next == previous {
+                break;
+            }
+            lengths.push(next);
+        }
+        Ok(Expansion {
+            pair_matrix,
+            template: template.to_vec(),
+            n_steps,
+            lengths,
+        })
+    }
+
+    fn lengths_at(&self, step: u64) -> &[u64] {
+        let last = self.lengths.len() - 1;
+        &self.lengths[(step.min(last as u64)) as usize]
+    }
+
+    fn template_pairs(&self) -> impl Iterator<Item = usize> + '_ {
+        self.template.windows(2).map(|pair| {
+            self.pair_matrix
+                .pair_index(pair[0], pair[1])
+                .expect("template letters are part of the alphabet")
+        })
+    }
+
+    fn len(&self) -> u64 {
+        let lengths = self.lengths_at(self.n_steps);
+        self.template_pairs()
+            .fold(self.template.len().min(1) as u64, |len, pair| {
+                len.saturating_add(lengths[pair])
+            })
+    }
+
+    // the elements at the given positions, or None if the range is not within the polymer
+    fn slice(&self, range: Range<u64>) -> Option<Vec<char>> {
+        if range.start > range.end || range.end > self.len() {
+            return None;
+        }
+        let mut result: Vec<char> = Vec::with_capacity((range.end - range.start) as usize);
+        let mut offset: u64 = 0;
+        for pair in self.template_pairs() {
+            if offset >= range.end {
+                break;
+            }
+            self.collect(pair, offset, &range, &mut result);
+            offset = offset.saturating_add(self.lengths_at(self.n_steps)[pair]);
+        }
+        // the last element of the template stays the last element of the polymer
+        if range.end > offset {
+            result.extend(self.template.last());
+        }
+        Some(result)
+    }
+
+    // Pushes the elements of the expanded template pair that are within the range, `offset` is the
+    // position of the first element of the expanded pair. This is a depth first search that skips
+    // everything outside of the range, with an explicit stack since there is one level per step.
+    // Only pairs that overlap the range are pushed, so the stack stays small even for many steps.
+    fn collect(&self, pair: usize, offset: u64, range: &Range<u64>, out: &mut Vec<char>) {
+        let n_letters = self.pair_matrix.alphabet.len();
+        let overlaps = |pair: usize, step: u64, offset: u64| {
+            offset < range.end && offset.saturating_add(self.lengths_at(step)[pair]) > range.start
+        };
+        let mut stack: Vec<(usize, u64, u64)> = Vec::new();
+        if overlaps(pair, self.n_steps, offset) {
+            stack.push((pair, self.n_steps, offset));
+        }
+        while let Some((pair, step, offset)) = stack.pop() {
+            match (step, self.pair_matrix.successors[pair]) {
+                (0, _) | (_, None) => out.push(self.pair_matrix.alphabet[pair / n_letters]),
+                (_, Some((first, second))) => {
+                    let second_offset = offset.saturating_add(self.lengths_at(step - 1)[first]);
+                    // the first half has to come out first
+                    if overlaps(second, step - 1, second_offset) {
+                        stack.push((second, step - 1, second_offset));
+                    }
+                    if overlaps(first, step - 1, offset) {
+                        stack.push((first, step - 1, offset));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_range(range: &str) -> Result<Range<u64>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("Unable to find '..' in range '{}'", range))?;
+    let start = start
+        .parse::<u64>()
+        .map_err(|e| format!("Unable to parse start of range '{}': {}", range, e))?;
+    let end = end
+        .parse::<u64>()
+        .map_err(|e| format!("Unable to parse end of range '{}': {}", range, e))?;
+    if start > end {
+        return Err(format!("Range '{}' ends before it starts", range));
+    }
+    Ok(start..end)
+}
+
+// Builds the whole polymer, which only works for a few steps. Good enough to check the other
+// solutions against.
+#[cfg(test)]
 fn grow_steps(
     template: Vec<char>,
     rules: &HashMap<(char, char), char>,
@@ -105,6 +462,7 @@ fn grow_steps(
     polymer
 }
 
+#[cfg(test)]
 fn grow_step(polymer: &[char], rules: &HashMap<(char, char), char>) -> Vec<char> {
     polymer
         .first()
@@ -128,9 +486,134 @@ fn grow_step(polymer: &[char], rules: &HashMap<(char, char), char>) -> Vec<char>
         .collect()
 }
 
-type Input = (Vec<char>, HashMap<(char, char), char>);
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum RuleIssue {
+    // a pair of the template has no rule, so it never grows
+    UncoveredTemplatePair((char, char)),
+    // the same rule appears again, lines are counted from 1
+    DuplicateRule {
+        pair: (char, char),
+        line: usize,
+        first_line: usize,
+    },
+    // a pair has rules with different insertions, the last one wins
+    ConflictingRule {
+        pair: (char, char),
+        inserted: char,
+        line: usize,
+        previous_inserted: char,
+        previous_line: usize,
+    },
+    // a pair that appears while growing the polymer has no rule
+    UncoveredReachablePair((char, char)),
+}
+
+impl fmt::Display for RuleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleIssue::UncoveredTemplatePair((a, b)) => {
+                write!(f, "no rule for pair {}{} of the template", a, b)
+            }
+            RuleIssue::DuplicateRule {
+                pair: (a, b),
+                line,
+                first_line,
+            } => write!(
+                f,
+                "rule for {}{} in line {} duplicates line {}",
+                a, b, line, first_line
+            ),
+            RuleIssue::ConflictingRule {
+                pair: (a, b),
+                inserted,
+                line,
+                previous_inserted,
+                previous_line,
+            } => write!(
+                f,
+                "rule {}{} -> {} in line {} conflicts with {}{} -> {} in line {}",
+                a, b, inserted, line, a, b, previous_inserted, previous_line
+            ),
+            RuleIssue::UncoveredReachablePair((a, b)) => {
+                write!(
+                    f,
+                    "no rule for pair {}{}, which appears while growing",
+                    a, b
+                )
+            }
+        }
+    }
+}
 
-fn parse(content: &str) -> Result<Input, String> {
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Validation {
+    // report issues, but go on with the rules as they are
+    Lenient,
+    // any issue is an error
+    Strict,
+}
+
+// `rules` are the parsed rules with their line numbers, in the order they appear in the input
+fn validate_rules(template: &[char], rules: &[(usize, (char, char), char)]) -> Vec<RuleIssue> {
+    let mut issues: Vec<RuleIssue> = Vec::new();
+
+    let mut seen: HashMap<(char, char), (usize, char)> = HashMap::with_capacity(rules.len());
+    for (line, pair, inserted) in rules.iter().copied() {
+        if let Some((previous_line, previous_inserted)) = seen.insert(pair, (line, inserted)) {
+            issues.push(if previous_inserted == inserted {
+                RuleIssue::DuplicateRule {
+                    pair,
+                    line,
+                    first_line: previous_line,
+                }
+            } else {
+                RuleIssue::ConflictingRule {
+                    pair,
+                    inserted,
+                    line,
+                    previous_inserted,
+                    previous_line,
+                }
+            });
+        }
+    }
+
+    let template_pairs: Vec<(char, char)> = template.windows(2).map(|w| (w[0], w[1])).collect();
+    let mut reported: HashSet<(char, char)> = HashSet::new();
+    for pair in template_pairs.iter().copied() {
+        if !seen.contains_key(&pair) && reported.insert(pair) {
+            issues.push(RuleIssue::UncoveredTemplatePair(pair));
+        }
+    }
+
+    // every pair that can ever show up, starting from the template
+    let mut reachable: HashSet<(char, char)> = template_pairs.iter().copied().collect();
+    let mut queue: Vec<(char, char)> = reachable.iter().copied().collect();
+    let mut uncovered: BTreeSet<(char, char)> = BTreeSet::new();
+    while let Some(pair) = queue.pop() {
+        match seen.get(&pair) {
+            Some((_, inserted)) => {
+                for next in [(pair.0, *inserted), (*inserted, pair.1)] {
+                    if reachable.insert(next) {
+                        queue.push(next);
+                    }
+                }
+            }
+            None => {
+                if !reported.contains(&pair) {
+                    uncovered.insert(pair);
+                }
+            }
+        }
+    }
+    issues.extend(uncovered.into_iter().map(RuleIssue::UncoveredReachablePair));
+
+    issues
+}
+
+type Input = (Vec<char>, HashMap<(char, char), char>, Vec<RuleIssue>);
+
+fn parse(content: &str, validation: Validation) -> Result<Input, String> {
     let mut lines = content.lines();
     let template: Vec<char> = lines
         .next()
@@ -142,11 +625,30 @@ fn parse(content: &str) -> Result<Input, String> {
         return Err("Expected empty line after polymer template".to_owned());
     }
 
-    let rules: HashMap<(char, char), char> = lines
-        .map(parse_rule)
-        .collect::<Result<HashMap<(char, char), char>, String>>()?;
+    // the rules start in the third line
+    let numbered_rules: Vec<(usize, (char, char), char)> = lines
+        .enumerate()
+        .map(|(i, line)| parse_rule(line).map(|(pair, inserted)| (i + 3, pair, inserted)))
+        .collect::<Result<Vec<(usize, (char, char), char)>, String>>()?;
+
+    let issues = validate_rules(&template, &numbered_rules);
+    if validation == Validation::Strict && !issues.is_empty() {
+        return Err(format!(
+            "Invalid rules: {}",
+            issues
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        ));
+    }
 
-    Ok((template, rules))
+    let rules: HashMap<(char, char), char> = numbered_rules
+        .into_iter()
+        .map(|(_, pair, inserted)| (pair, inserted))
+        .collect();
+
+    Ok((template, rules, issues))
 }
 
 fn parse_rule(line: &str) -> Result<((char, char), char), String> {
@@ -198,10 +700,173 @@ CC -> N
 CN -> C
 ";
 
+    #[test]
+    fn expansion_slice_matches_grow_steps() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+        let polymer = grow_steps(template.clone(), &rules, 10);
+        let expansion = Expansion::new(&template, &rules, 10).expect("Expected expansion");
+
+        // when
+        let slices: Vec<(Range<u64>, Option<Vec<char>>)> = [0..0, 0..10, 17..1000, 3000..3073]
+            .into_iter()
+            .map(|range| (range.clone(), expansion.slice(range)))
+            .collect();
+
+        // then
+        assert_eq!(expansion.len(), polymer.len() as u64);
+        for (range, slice) in slices {
+            assert_eq!(
+                slice,
+                Some(polymer[range.start as usize..range.end as usize].to_vec())
+            );
+        }
+        assert_eq!(expansion.slice(3000..3074), None);
+    }
+
+    #[test]
+    fn expansion_works_for_40_steps() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+        let expansion = Expansion::new(&template, &rules, 40).expect("Expected expansion");
+        let middle = expansion.len() / 2;
+
+        // when
+        let whole = expansion.slice(middle - 10..middle + 10);
+        let first_half = expansion.slice(middle - 10..middle);
+        let second_half = expansion.slice(middle..middle + 10);
+        let end = expansion.slice(expansion.len() - 1..expansion.len());
+
+        // then
+        // the length is 3 * 2^40 + 1
+        assert_eq!(expansion.len(), 3298534883329);
+        let mut halves = first_half.expect("Expected slice in range");
+        halves.extend(second_half.expect("Expected slice in range"));
+        assert_eq!(whole, Some(halves));
+        assert_eq!(end, Some(vec!['B']));
+    }
+
+    #[test]
+    fn expansion_works_for_many_steps() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+        let expansion = Expansion::new(&template, &rules, 100000).expect("Expected expansion");
+        // NN -> NC N, NC -> NB C, NB -> NB B, so the polymer always starts with the expansion of NB,
+        // and its first 10 elements do not change anymore after a few steps
+        let polymer = grow_steps(template.clone(), &rules, 12);
+
+        // when
+        let prefix = expansion.slice(0..10);
+
+        // then
+        assert!(expansion.lengths.len() < 100);
+        assert_eq!(expansion.len(), u64::MAX);
+        assert_eq!(prefix, Some(polymer[0..10].to_vec()));
+    }
+
+    #[test]
+    fn expansion_works_for_linear_growth() {
+        // given
+        let (template, rules, _) =
+            parse("AB\n\nAB -> A\n", Validation::Lenient).expect("Expected successful parseing");
+
+        // when
+        let expansion = Expansion::new(&template, &rules, 1000).expect("Expected expansion");
+        let too_many_steps = Expansion::new(&template, &rules, 100_000_000);
+
+        // then
+        // AB -> AAB -> AAAB, one more element per step
+        assert_eq!(expansion.len(), 1002);
+        assert_eq!(expansion.slice(0..3), Some(vec!['A', 'A', 'A']));
+        assert_eq!(expansion.slice(1000..1002), Some(vec!['A', 'B']));
+        assert!(too_many_steps.is_err());
+    }
+
+    #[test]
+    fn parse_range_rejects_backwards_ranges() {
+        assert_eq!(parse_range("3..7"), Ok(3..7));
+        assert!(parse_range("7..3").is_err());
+    }
+
+    #[test]
+    fn expansion_leaves_pairs_without_rule_alone() {
+        // given
+        let (template, rules, _) =
+            parse("ABA\n\nAB -> C\n", Validation::Lenient).expect("Expected successful parseing");
+
+        // when
+        let expansion = Expansion::new(&template, &rules, 5).expect("Expected expansion");
+
+        // then
+        assert_eq!(expansion.slice(0..4), Some(vec!['A', 'C', 'B', 'A']));
+    }
+
+    #[test]
+    fn parse_finds_no_issues_in_example() {
+        // when
+        let result = parse(EXAMPLE_INPUT, Validation::Lenient);
+
+        // then
+        assert_eq!(result.map(|(_, _, issues)| issues), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_reports_rule_issues() {
+        // given
+        let input = "ABAD\n\nAB -> C\nAB -> C\nCB -> A\nCB -> B\nBA -> A\n";
+
+        // when
+        let (_, rules, issues) =
+            parse(input, Validation::Lenient).expect("Expected successful parseing");
+
+        // then
+        assert_eq!(
+            issues,
+            vec![
+                RuleIssue::DuplicateRule {
+                    pair: ('A', 'B'),
+                    line: 4,
+                    first_line: 3,
+                },
+                RuleIssue::ConflictingRule {
+                    pair: ('C', 'B'),
+                    inserted: 'B',
+                    line: 6,
+                    previous_inserted: 'A',
+                    previous_line: 5,
+                },
+                RuleIssue::UncoveredTemplatePair(('A', 'D')),
+                RuleIssue::UncoveredReachablePair(('A', 'A')),
+                RuleIssue::UncoveredReachablePair(('A', 'C')),
+                RuleIssue::UncoveredReachablePair(('B', 'B')),
+            ]
+        );
+        assert_eq!(rules.get(&('C', 'B')), Some(&'B'));
+    }
+
+    #[test]
+    fn parse_fails_for_rule_issues_in_strict_mode() {
+        // when
+        let result = parse("ABA\n\nAB -> C\n", Validation::Strict);
+
+        // then
+        assert_eq!(
+            result,
+            Err("Invalid rules: no rule for pair BA of the template; \
+                 no rule for pair AC, which appears while growing; \
+                 no rule for pair CB, which appears while growing"
+                .to_owned())
+        );
+    }
+
     #[test]
     fn grow_steps_works_for_example() {
         // given
-        let (template, rules) = parse(EXAMPLE_INPUT).expect("Expected successful parseing");
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
 
         // when
         let polymer = grow_steps(template, &rules, 4);
@@ -216,14 +881,97 @@ CN -> C
     }
 
     #[test]
-    fn score_after_steps_works_for_example() {
+    fn histogram_after_steps_works_for_example() {
+        for engine in [Engine::Stepwise, Engine::MatrixPower] {
+            // given
+            let (template, rules, _) =
+                parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+
+            // when
+            let histogram = histogram_after_steps(&template, &rules, 40, engine);
+
+            // then
+            assert_eq!(histogram[&'B'].to_string(), "2192039569602");
+            assert_eq!(histogram[&'H'].to_string(), "3849876073");
+            assert_eq!(
+                score_histogram(&histogram).map(|score| score.to_string()),
+                Some("2188189693529".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn score_histogram_works_for_example_after_10_steps() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+        let histogram = histogram_after_steps(&template, &rules, 10, Engine::Stepwise);
+
+        // when
+        let score = score_histogram(&histogram);
+
+        // then
+        assert_eq!(score, Some(BigUint::from(1588)));
+    }
+
+    #[test]
+    fn histogram_after_steps_matches_grow_steps() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+
+        for n_steps in [0, 1, 4, 10] {
+            // when
+            let stepwise = histogram_after_steps(&template, &rules, n_steps, Engine::Stepwise);
+            let matrix_power =
+                histogram_after_steps(&template, &rules, n_steps, Engine::MatrixPower);
+
+            // then
+            let polymer = grow_steps(template.clone(), &rules, n_steps as usize);
+            let mut expected: Histogram = BTreeMap::new();
+            for c in polymer {
+                let entry = expected.entry(c).or_insert_with(BigUint::zero);
+                *entry = &*entry + &BigUint::from(1);
+            }
+            assert_eq!(stepwise, expected);
+            assert_eq!(matrix_power, expected);
+        }
+    }
+
+    #[test]
+    fn histogram_after_steps_works_beyond_u64() {
+        // given
+        let (template, rules, _) =
+            parse(EXAMPLE_INPUT, Validation::Strict).expect("Expected successful parseing");
+
+        // when
+        let stepwise = histogram_after_steps(&template, &rules, 100, Engine::Stepwise);
+        let matrix_power = histogram_after_steps(&template, &rules, 100, Engine::MatrixPower);
+
+        // then
+        // the polymer length is 3 * 2^100 + 1
+        let total = stepwise
+            .values()
+            .fold(BigUint::zero(), |sum, count| &sum + count);
+        assert_eq!(total.to_string(), "3802951800684688204490109616129");
+        assert_eq!(stepwise, matrix_power);
+    }
+
+    #[test]
+    fn histogram_after_steps_leaves_pairs_without_rule_alone() {
         // given
-        let (template, rules) = parse(EXAMPLE_INPUT).expect("Expected successful parseing");
+        let (template, rules, _) =
+            parse("ABA\n\nAB -> C\n", Validation::Lenient).expect("Expected successful parseing");
 
         // when
-        let score = score_after_steps(&template, &rules, 40);
+        let histogram = histogram_after_steps(&template, &rules, 3, Engine::MatrixPower);
 
         // then
-        assert_eq!(score, Some(2188189693529));
+        // ABA -> ACBA, and nothing happens after that
+        let expected: Histogram = [('A', 2), ('B', 1), ('C', 1)]
+            .into_iter()
+            .map(|(c, n)| (c, BigUint::from(n)))
+            .collect();
+        assert_eq!(histogram, expected);
     }
 }
@@ -1,63 +1,205 @@
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashSet};
 use std::env;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write};
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 fn main() -> Result<(), String> {
     let filename = env::args()
         .nth(1)
         .ok_or_else(|| "No file name given.".to_owned())?;
-    let content = read_to_string(&Path::new(&filename)).map_err(|e| e.to_string())?;
+    let mut ascii = false;
+    let mut ppm_prefix: Option<String> = None;
+    let mut factor: usize = 5;
+    let mut materialise = false;
+    let mut algorithm = Algorithm::BinaryHeap;
+    let mut benchmark_runs: Option<u32> = None;
+    for arg in env::args().skip(2) {
+        if arg == "--ascii" {
+            ascii = true;
+        } else if let Some(prefix) = arg.strip_prefix("--ppm=") {
+            ppm_prefix = Some(prefix.to_owned());
+        } else if let Some(f) = arg.strip_prefix("--factor=") {
+            factor = f
+                .parse()
+                .map_err(|e| format!("Unable to parse expansion factor: {}", e))?;
+            if factor == 0 {
+                return Err("The expansion factor must be at least 1".to_owned());
+            }
+        } else if arg == "--materialise" {
+            materialise = true;
+        } else if let Some(name) = arg.strip_prefix("--algorithm=") {
+            algorithm = parse_algorithm(name)?;
+        } else if let Some(runs) = arg.strip_prefix("--benchmark=") {
+            benchmark_runs = Some(
+                runs.parse()
+                    .map_err(|e| format!("Unable to parse number of benchmark runs: {}", e))?,
+            );
+        } else {
+            return Err(format!("Unknown argument '{}'", arg));
+        }
+    }
+    let content = read_to_string(Path::new(&filename)).map_err(|e| e.to_string())?;
 
     let cavern = parse(&content)?;
+    if let Some(runs) = benchmark_runs {
+        benchmark(&expand_cavern(&cavern, 5), runs);
+        return Ok(());
+    }
+
+    let output = Output {
+        ascii,
+        ppm_prefix: ppm_prefix.as_deref(),
+        algorithm,
+    };
 
-    if let Some(risk) = shortest_path(&cavern, 0, cavern.risk.len() - 1) {
-        println!("The least risky path has a risk value of {}", risk);
+    output.report_shortest_path(&cavern, 1)?;
+    if materialise {
+        // faster, as long as it fits into memory
+        output.report_shortest_path(&expand_cavern(&cavern, factor), factor)?;
     } else {
-        println!("There is no path, risky or otherwise. Are you sure you did not mess up your pathfinding alogorithm?");
+        output.report_shortest_path(&TiledCavern::new(&cavern, factor), factor)?;
     }
 
-    let large_cavern = expand_cavern(&cavern, 5);
-    if let Some(risk) = shortest_path(&large_cavern, 0, large_cavern.risk.len() - 1) {
+    Ok(())
+}
+
+struct Output<'a> {
+    ascii: bool,
+    ppm_prefix: Option<&'a str>,
+    algorithm: Algorithm,
+}
+
+impl Output<'_> {
+    fn report_shortest_path<M: RiskMap>(&self, cavern: &M, factor: usize) -> Result<(), String> {
+        let path = match find_path(cavern, 0, cavern.n_cells() - 1, self.algorithm) {
+            Some(path) => path,
+            None => {
+                println!("There is no path, risky or otherwise. Are you sure you did not mess up your pathfinding alogorithm?");
+                return Ok(());
+            }
+        };
+        if factor == 1 {
+            println!("The least risky path has a risk value of {}", path.risk);
+        } else {
+            println!(
+                "The least risky path through the {}x cavern has a risk value of {}",
+                factor, path.risk
+            );
+        }
+        if self.ascii {
+            println!("{}", render_path_ascii(cavern, &path));
+        }
+        if let Some(prefix) = self.ppm_prefix {
+            write(
+                Path::new(&format!("{}-{}x.ppm", prefix, factor)),
+                render_path_ppm(cavern, &path),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+// runs both algorithms on the same cavern and prints the average time of each
+fn benchmark(cavern: &Cavern, runs: u32) {
+    let goal = cavern.n_cells() - 1;
+    for algorithm in [Algorithm::BinaryHeap, Algorithm::BucketQueue] {
+        let mut total = Duration::ZERO;
+        let mut risk: Option<u32> = None;
+        for _ in 0..runs {
+            let start_time = Instant::now();
+            let path = find_path(cavern, 0, goal, algorithm);
+            total += start_time.elapsed();
+            risk = path.map(|p| p.risk);
+        }
         println!(
-            "The least risky path through the large cavern has a risk value of {}",
-            risk
+            "{:?}: risk {:?}, {:?} per run on average over {} runs",
+            algorithm,
+            risk,
+            total / runs.max(1),
+            runs
         );
-    } else {
-        println!("There is no path, risky or otherwise. Are you sure you did not mess up your pathfinding alogorithm?");
     }
+}
 
-    Ok(())
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Algorithm {
+    // Dijkstra with a binary heap, works for any risks
+    BinaryHeap,
+    // Dial's algorithm: Dijkstra with one bucket per distance, for small integer risks
+    BucketQueue,
+}
+
+fn parse_algorithm(name: &str) -> Result<Algorithm, String> {
+    match name {
+        "heap" => Ok(Algorithm::BinaryHeap),
+        "buckets" => Ok(Algorithm::BucketQueue),
+        _ => Err(format!(
+            "Unknown algorithm '{}', expected 'heap' or 'buckets'",
+            name
+        )),
+    }
+}
+
+fn find_path<M: RiskMap>(
+    cavern: &M,
+    start: usize,
+    goal: usize,
+    algorithm: Algorithm,
+) -> Option<CavePath> {
+    match algorithm {
+        Algorithm::BinaryHeap => shortest_path(cavern, start, goal),
+        Algorithm::BucketQueue => bucket_shortest_path(cavern, start, goal),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct CavePath {
+    risk: u32,
+    // from start to goal, both included
+    cells: Vec<usize>,
 }
 
-fn shortest_path(cavern: &Cavern, start: usize, goal: usize) -> Option<u32> {
-    let mut queue: BinaryHeap<VerticeDistance> = BinaryHeap::with_capacity(cavern.risk.len());
+fn shortest_path<M: RiskMap>(cavern: &M, start: usize, goal: usize) -> Option<CavePath> {
+    let mut queue: BinaryHeap<VerticeDistance> = BinaryHeap::new();
     queue.push(VerticeDistance {
         dist: 0,
         pos: start,
+        from: start,
     });
 
-    let mut visited: HashMap<usize, u32> = HashMap::with_capacity(cavern.risk.len());
+    // one byte per cell instead of a map of positions, so huge caverns still fit into memory
+    let mut visited: Vec<Predecessor> = vec![Predecessor::Unvisited; cavern.n_cells()];
 
-    while let Some(VerticeDistance { dist: d, pos: v }) = queue.pop() {
-        if v == goal {
-            return Some(d);
-        }
-        if visited.contains_key(&v) {
+    while let Some(VerticeDistance {
+        dist: d,
+        pos: v,
+        from,
+    }) = queue.pop()
+    {
+        if visited[v] != Predecessor::Unvisited {
             // since we cannot easily remove deprecated entries on the heap, we just skip them when
             // they are popped
             continue;
         }
-        visited.insert(v, d);
+        visited[v] = Predecessor::new(v, from, cavern.width());
+        if v == goal {
+            return Some(CavePath {
+                risk: d,
+                cells: backtrack(&visited, goal, cavern.width()),
+            });
+        }
         for neighbour in cavern.von_neumann_neighbours(v) {
-            if !visited.contains_key(&neighbour) {
-                let risk = d + cavern.risk[neighbour] as u32;
+            if visited[neighbour] == Predecessor::Unvisited {
+                let risk = d + cavern.risk_at(neighbour) as u32;
                 // we can't really update the distance for a given position in the queue, but we
                 // can just add all unvisited neighbours and later skip duplicates
                 queue.push(VerticeDistance {
                     dist: risk,
                     pos: neighbour,
+                    from: v,
                 });
             }
         }
@@ -65,11 +207,140 @@ fn shortest_path(cavern: &Cavern, start: usize, goal: usize) -> Option<u32> {
     None
 }
 
+// Risks are single digits, so all tentative distances in the queue are within 9 of the current
+// one. Ten buckets of (position, predecessor) indexed by distance modulo 10 are enough, and
+// there is no need to keep anything sorted.
+fn bucket_shortest_path<M: RiskMap>(cavern: &M, start: usize, goal: usize) -> Option<CavePath> {
+    const N_BUCKETS: usize = 10;
+    let mut buckets: Vec<Vec<(usize, usize)>> = vec![Vec::new(); N_BUCKETS];
+    buckets[0].push((start, start));
+    let mut queued: usize = 1;
+    let mut visited: Vec<Predecessor> = vec![Predecessor::Unvisited; cavern.n_cells()];
+
+    let mut d: u32 = 0;
+    while queued > 0 {
+        let bucket = d as usize % N_BUCKETS;
+        // risk 0 cells end up in the current bucket again, so take one at a time
+        while let Some((v, from)) = buckets[bucket].pop() {
+            queued -= 1;
+            if visited[v] != Predecessor::Unvisited {
+                // like with the heap, outdated entries are skipped instead of removed
+                continue;
+            }
+            visited[v] = Predecessor::new(v, from, cavern.width());
+            if v == goal {
+                return Some(CavePath {
+                    risk: d,
+                    cells: backtrack(&visited, goal, cavern.width()),
+                });
+            }
+            for neighbour in cavern.von_neumann_neighbours(v) {
+                if visited[neighbour] == Predecessor::Unvisited {
+                    let risk = d + cavern.risk_at(neighbour) as u32;
+                    buckets[risk as usize % N_BUCKETS].push((neighbour, v));
+                    queued += 1;
+                }
+            }
+        }
+        d += 1;
+    }
+    None
+}
+
+// where the shortest path to a cell comes from
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Predecessor {
+    Unvisited,
+    Start,
+    Above,
+    Left,
+    Right,
+    Below,
+}
+
+impl Predecessor {
+    fn new(pos: usize, from: usize, width: usize) -> Predecessor {
+        if from == pos {
+            Predecessor::Start
+        } else if from + width == pos {
+            Predecessor::Above
+        } else if from + 1 == pos {
+            Predecessor::Left
+        } else if pos + 1 == from {
+            Predecessor::Right
+        } else {
+            Predecessor::Below
+        }
+    }
+
+    fn from(self, pos: usize, width: usize) -> Option<usize> {
+        match self {
+            Predecessor::Unvisited | Predecessor::Start => None,
+            Predecessor::Above => Some(pos - width),
+            Predecessor::Left => Some(pos - 1),
+            Predecessor::Right => Some(pos + 1),
+            Predecessor::Below => Some(pos + width),
+        }
+    }
+}
+
+fn backtrack(visited: &[Predecessor], goal: usize, width: usize) -> Vec<usize> {
+    let mut cells: Vec<usize> = vec![goal];
+    let mut current = goal;
+    while let Some(previous) = visited[current].from(current, width) {
+        cells.push(previous);
+        current = previous;
+    }
+    cells.reverse();
+    cells
+}
+
+// cells on the path show their risk, everything else is a dot
+fn render_path_ascii<M: RiskMap>(cavern: &M, path: &CavePath) -> String {
+    let on_path: HashSet<usize> = path.cells.iter().copied().collect();
+    let mut result = String::with_capacity(cavern.n_cells() + cavern.height());
+    for i in 0..cavern.n_cells() {
+        if on_path.contains(&i) {
+            result.push((b'0' + cavern.risk_at(i)) as char);
+        } else {
+            result.push('.');
+        }
+        if i % cavern.width() == cavern.width() - 1 {
+            result.push('\n');
+        }
+    }
+    result
+}
+
+// plain text PPM, one pixel per cell: the higher the risk, the darker the cell, the path is red
+fn render_path_ppm<M: RiskMap>(cavern: &M, path: &CavePath) -> String {
+    let on_path: HashSet<usize> = path.cells.iter().copied().collect();
+    let mut result = format!("P3\n{} {}\n255\n", cavern.width(), cavern.height());
+    for row in 0..cavern.height() {
+        let pixels: Vec<String> = (0..cavern.width())
+            .map(|column| {
+                let i = row * cavern.width() + column;
+                if on_path.contains(&i) {
+                    "255 0 0".to_owned()
+                } else {
+                    let grey = 255 - cavern.risk_at(i) as u32 * 255 / 9;
+                    format!("{} {} {}", grey, grey, grey)
+                }
+            })
+            .collect();
+        result.push_str(&pixels.join(" "));
+        result.push('\n');
+    }
+    result
+}
+
 // This is required to use BTreeSet as halfway efficient priority queue
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 struct VerticeDistance {
     dist: u32,
     pos: usize,
+    // where we came from, to find the way back
+    from: usize,
 }
 
 impl PartialOrd for VerticeDistance {
@@ -82,7 +353,7 @@ impl PartialOrd for VerticeDistance {
 impl Ord for VerticeDistance {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.dist.cmp(&other.dist) {
-            Ordering::Equal => self.pos.cmp(&other.pos),
+            Ordering::Equal => (self.pos, self.from).cmp(&(other.pos, other.from)),
             ne => ne,
         }
         .reverse() // reverse the ordering because BinaryHeap gives out max values first
@@ -90,20 +361,71 @@ impl Ord for VerticeDistance {
 }
 
 fn expand_cavern(cavern: &Cavern, factor: usize) -> Cavern {
-    let source_height = cavern.risk.len() / cavern.width;
-    let width = cavern.width * factor;
-    let risk: Vec<u8> = (0..cavern.risk.len() * factor * factor)
-        .map(|i| {
-            let x = i % width;
-            let y = i / width;
-            let source_x = x % cavern.width;
-            let source_y = y % source_height;
-            let source_risk = cavern.risk[source_x + cavern.width * source_y];
-            let risk = source_risk as usize + x / cavern.width + y / source_height;
-            (if risk > 9 { risk % 10 + 1 } else { risk }) as u8
-        })
-        .collect();
-    Cavern { width, risk }
+    let tiled = TiledCavern::new(cavern, factor);
+    Cavern {
+        width: tiled.width(),
+        risk: (0..tiled.n_cells()).map(|pos| tiled.risk_at(pos)).collect(),
+    }
+}
+
+// The cavern repeated `factor` times in both directions, where the risk increases by one for every
+// tile to the right or down, wrapping around from 9 to 1. Only the original tile is kept in
+// memory, everything else is computed when needed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct TiledCavern<'a> {
+    tile: &'a Cavern,
+    factor: usize,
+}
+
+impl TiledCavern<'_> {
+    fn new(tile: &Cavern, factor: usize) -> TiledCavern<'_> {
+        TiledCavern { tile, factor }
+    }
+}
+
+impl RiskMap for TiledCavern<'_> {
+    fn width(&self) -> usize {
+        self.tile.width * self.factor
+    }
+
+    fn height(&self) -> usize {
+        self.tile.height() * self.factor
+    }
+
+    fn risk_at(&self, pos: usize) -> u8 {
+        let x = pos % self.width();
+        let y = pos / self.width();
+        let source_risk =
+            self.tile.risk[x % self.tile.width + self.tile.width * (y % self.tile.height())];
+        let risk = source_risk as usize + x / self.tile.width + y / self.tile.height();
+        // a risk of 0 can only happen in the original tile, and it stays 0 there
+        if risk == 0 {
+            0
+        } else {
+            ((risk - 1) % 9 + 1) as u8
+        }
+    }
+}
+
+// Anything that has a risk for every cell. The search does not care whether the cells are in
+// memory or not.
+trait RiskMap {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn risk_at(&self, pos: usize) -> u8;
+
+    fn n_cells(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    fn von_neumann_neighbours(&self, pos: usize) -> VonNeumannNeighbours {
+        VonNeumannNeighbours {
+            width: self.width() as isize,
+            height: self.height() as isize,
+            center: pos as isize,
+            neighbour_index: 0,
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
@@ -124,7 +446,7 @@ fn parse(input: &str) -> Result<Cavern, String> {
         .filter_map(|c| c.to_digit(10).map(|d| d as u8))
         .collect();
 
-    if risk.len() % width != 0 {
+    if !risk.len().is_multiple_of(width) {
         Err(format!(
             "The cavern does not seem to be a rectangle: area: {}, width: {}",
             risk.len(),
@@ -137,17 +459,22 @@ fn parse(input: &str) -> Result<Cavern, String> {
 // I know I don't really need an iterator for that but I want one!
 struct VonNeumannNeighbours {
     width: isize,
+    height: isize,
     center: isize,
     neighbour_index: usize,
 }
 
-impl Cavern {
-    fn von_neumann_neighbours(&self, pos: usize) -> VonNeumannNeighbours {
-        VonNeumannNeighbours {
-            width: self.width as isize,
-            center: pos as isize,
-            neighbour_index: 0,
-        }
+impl RiskMap for Cavern {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.risk.len() / self.width
+    }
+
+    fn risk_at(&self, pos: usize) -> u8 {
+        self.risk[pos]
     }
 }
 
@@ -162,7 +489,7 @@ impl Iterator for VonNeumannNeighbours {
             self.neighbour_index += 1;
             let x = self.center % self.width + dx;
             let y = self.center / self.width + dy;
-            if x < 0 || y < 0 || x >= self.width || y >= self.width {
+            if x < 0 || y < 0 || x >= self.width || y >= self.height {
                 continue;
             } else {
                 return Some((x + y * self.width) as usize);
@@ -197,7 +524,162 @@ mod test {
         let least_risk = shortest_path(&cavern, 0, cavern.risk.len() - 1);
 
         // then
-        assert_eq!(least_risk, Some(40));
+        assert_eq!(least_risk.map(|path| path.risk), Some(40));
+    }
+
+    #[test]
+    fn shortest_path_finds_path_for_example() {
+        // given
+        let cavern = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+
+        // when
+        let path = shortest_path(&cavern, 0, cavern.risk.len() - 1).expect("Expected a path");
+
+        // then
+        assert_eq!(path.cells.first(), Some(&0));
+        assert_eq!(path.cells.last(), Some(&99));
+        for step in path.cells.windows(2) {
+            assert!(cavern.von_neumann_neighbours(step[0]).any(|n| n == step[1]));
+        }
+        let risk: u32 = path.cells[1..].iter().map(|i| cavern.risk[*i] as u32).sum();
+        assert_eq!(risk, path.risk);
+    }
+
+    #[test]
+    fn shortest_path_finds_path_in_expanded_example() {
+        // given
+        let cavern = expand_cavern(
+            &parse(EXAMPLE_INPUT).expect("Expected successful parsing"),
+            5,
+        );
+
+        // when
+        let path = shortest_path(&cavern, 0, cavern.risk.len() - 1).expect("Expected a path");
+
+        // then
+        assert_eq!(path.risk, 315);
+        let risk: u32 = path.cells[1..].iter().map(|i| cavern.risk[*i] as u32).sum();
+        assert_eq!(risk, path.risk);
+    }
+
+    #[test]
+    fn tiled_cavern_matches_expand_cavern() {
+        // given
+        let cavern = parse("81\n29\n47\n").expect("Expected successful parsing");
+
+        // when
+        let tiled = TiledCavern::new(&cavern, 4);
+        let expanded = expand_cavern(&cavern, 4);
+
+        // then
+        assert_eq!(tiled.width(), 8);
+        assert_eq!(tiled.height(), 12);
+        let tiled_risk: Vec<u8> = (0..tiled.n_cells()).map(|pos| tiled.risk_at(pos)).collect();
+        assert_eq!(tiled_risk, expanded.risk);
+    }
+
+    #[test]
+    fn tiled_cavern_keeps_zero_risk_in_original_tile() {
+        // given
+        let cavern = parse("10\n11\n").expect("Expected successful parsing");
+
+        // when
+        let tiled = TiledCavern::new(&cavern, 2);
+        let path = shortest_path(&tiled, 0, tiled.n_cells() - 1);
+
+        // then
+        let tiled_risk: Vec<u8> = (0..tiled.n_cells()).map(|pos| tiled.risk_at(pos)).collect();
+        assert_eq!(
+            tiled_risk,
+            vec![1, 0, 2, 1, 1, 1, 2, 2, 2, 1, 3, 2, 2, 2, 3, 3]
+        );
+        // e.g. right, down, down, down, right, right: 0 + 1 + 1 + 2 + 3 + 3
+        assert_eq!(path.map(|p| p.risk), Some(10));
+        assert_eq!(
+            shortest_path(&expand_cavern(&cavern, 2), 0, 15).map(|p| p.risk),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn shortest_path_works_for_tiled_example() {
+        // given
+        let cavern = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+        let tiled = TiledCavern::new(&cavern, 5);
+
+        // when
+        let path = shortest_path(&tiled, 0, tiled.n_cells() - 1).expect("Expected a path");
+
+        // then
+        assert_eq!(path.risk, 315);
+        assert_eq!(
+            Some(path),
+            shortest_path(&expand_cavern(&cavern, 5), 0, 2499)
+        );
+    }
+
+    #[test]
+    fn shortest_path_works_for_non_square_cavern() {
+        // given
+        let cavern = parse("1999\n1111\n9991\n").expect("Expected successful parsing");
+
+        // when
+        let path = shortest_path(&cavern, 0, 11).expect("Expected a path");
+
+        // then
+        assert_eq!(path.risk, 5);
+        assert_eq!(path.cells, vec![0, 4, 5, 6, 7, 11]);
+    }
+
+    #[test]
+    fn bucket_shortest_path_matches_shortest_path() {
+        // given
+        let cavern = parse(EXAMPLE_INPUT).expect("Expected successful parsing");
+        let tiled = TiledCavern::new(&cavern, 5);
+        let zero_risk = parse("100\n090\n000\n").expect("Expected successful parsing");
+
+        // when
+        let small = bucket_shortest_path(&cavern, 0, cavern.n_cells() - 1);
+        let large = bucket_shortest_path(&tiled, 0, tiled.n_cells() - 1);
+        let zero = bucket_shortest_path(&zero_risk, 0, 8);
+
+        // then
+        assert_eq!(small.as_ref().map(|path| path.risk), Some(40));
+        assert_eq!(large.as_ref().map(|path| path.risk), Some(315));
+        assert_eq!(zero.as_ref().map(|path| path.risk), Some(0));
+        for (path, cavern) in [(small, &cavern), (large, &expand_cavern(&cavern, 5))] {
+            let path = path.expect("Expected a path");
+            let risk: u32 = path.cells[1..].iter().map(|i| cavern.risk[*i] as u32).sum();
+            assert_eq!(risk, path.risk);
+        }
+    }
+
+    #[test]
+    fn bucket_shortest_path_finds_nothing_for_goal_outside_cavern() {
+        // given
+        let cavern = parse("12\n").expect("Expected successful parsing");
+
+        // when
+        let path = bucket_shortest_path(&cavern, 0, 2);
+
+        // then
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn render_path_works_for_small_cavern() {
+        // given
+        let cavern = parse("19\n11\n").expect("Expected successful parsing");
+        let path = shortest_path(&cavern, 0, 3).expect("Expected a path");
+
+        // when
+        let ascii = render_path_ascii(&cavern, &path);
+        let ppm = render_path_ppm(&cavern, &path);
+
+        // then
+        assert_eq!(path.cells, vec![0, 2, 3]);
+        assert_eq!(ascii, "1.\n11\n");
+        assert_eq!(ppm, "P3\n2 2\n255\n255 0 0 0 0 0\n255 0 0 255 0 0\n");
     }
 
     #[test]